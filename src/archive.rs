@@ -0,0 +1,104 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
+
+use flate2::read::GzDecoder;
+use tar;
+use zip::ZipArchive;
+
+use {get_language, Language};
+
+enum Kind {
+    Tar,
+    TarGz,
+    Zip,
+    Gz,
+}
+
+fn archive_kind(filepath: &str) -> Option<Kind> {
+    let filename = Path::new(filepath).file_name()?.to_str()?.to_lowercase();
+    if filename.ends_with(".tar.gz") || filename.ends_with(".tgz") {
+        Some(Kind::TarGz)
+    } else if filename.ends_with(".tar") {
+        Some(Kind::Tar)
+    } else if filename.ends_with(".zip") {
+        Some(Kind::Zip)
+    } else if filename.ends_with(".gz") {
+        Some(Kind::Gz)
+    } else {
+        None
+    }
+}
+
+pub fn is_archive(filepath: &str) -> bool {
+    archive_kind(filepath).is_some()
+}
+
+// Calls visit with the path and contents of every file in the archive that is
+// in a known language. Paths are given below the archive path, as if the
+// archive was a directory. Other files are skipped without decompressing them.
+pub fn read_archive<F>(filepath: &str, mut visit: F) -> io::Result<()>
+where
+    F: FnMut(String, Vec<u8>),
+{
+    let kind = match archive_kind(filepath) {
+        Some(kind) => kind,
+        None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "not an archive")),
+    };
+
+    let file = BufReader::new(File::open(filepath)?);
+
+    match kind {
+        Kind::Tar => read_tar(filepath, file, &mut visit),
+        Kind::TarGz => read_tar(filepath, GzDecoder::new(file), &mut visit),
+        Kind::Zip => {
+            let mut archive = ZipArchive::new(file)?;
+            for i in 0..archive.len() {
+                let mut entry = archive.by_index(i)?;
+                let path = format!("{}/{}", filepath, entry.name());
+                if entry.is_dir() || get_language(&path) == Language::Unknown {
+                    continue;
+                }
+                let mut bytes = vec![];
+                entry.read_to_end(&mut bytes)?;
+                visit(path, bytes);
+            }
+            Ok(())
+        }
+        Kind::Gz => {
+            let name = Path::new(filepath).file_stem().map_or(String::new(), |stem| {
+                stem.to_string_lossy().into_owned()
+            });
+            let path = format!("{}/{}", filepath, name);
+            if get_language(&path) == Language::Unknown {
+                return Ok(());
+            }
+            let mut bytes = vec![];
+            GzDecoder::new(file).read_to_end(&mut bytes)?;
+            visit(path, bytes);
+            Ok(())
+        }
+    }
+}
+
+fn read_tar<R, F>(filepath: &str, reader: R, visit: &mut F) -> io::Result<()>
+where
+    R: Read,
+    F: FnMut(String, Vec<u8>),
+{
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = format!("{}/{}", filepath, entry.path()?.to_string_lossy());
+        if get_language(&path) == Language::Unknown {
+            continue;
+        }
+        let mut bytes = vec![];
+        entry.read_to_end(&mut bytes)?;
+        visit(path, bytes);
+    }
+    Ok(())
+}
@@ -0,0 +1,440 @@
+use std::ops::Range;
+
+use {get_doc_markers, get_language_config, get_string_syntax, get_template_config};
+use {ByteLines, ByteLinesState, Count, Language, LineConfig, StringSyntax};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LineKind {
+    Code,
+    Comment,
+    Doc,
+    Blank,
+    // Code followed or preceded by a comment, counted as code
+    Mixed,
+}
+
+#[derive(Debug, Clone)]
+pub struct Line<'a> {
+    // Line numbers start at 1
+    pub number: u32,
+    // The line without its newline
+    pub text: &'a [u8],
+    pub kind: LineKind,
+    // Byte ranges of the comments on the line, markers included
+    pub comments: Vec<Range<usize>>,
+    // Byte ranges of the contents of the string and char literals on the line,
+    // quotes excluded. A string still open from the line before starts at 0.
+    pub strings: Vec<Range<usize>>,
+    // End marker of the multiline comment, verbatim section or string still
+    // open after the line
+    pub open: Option<&'a str>,
+}
+
+#[derive(Clone, Copy)]
+enum Open<'a> {
+    Nothing,
+    Comment { end: &'a str, doc: bool },
+    Verbatim(&'a str),
+    String(&'static str),
+}
+
+pub struct Lines<'a> {
+    lines: ByteLinesState<'a>,
+    number: u32,
+    singles: Vec<&'a str>,
+    multis: Vec<(&'a str, &'a str)>,
+    verbatim: Option<(&'a str, &'a str)>,
+    docs: &'a [&'a str],
+    strings: StringSyntax,
+    open: Open<'a>,
+}
+
+pub fn classify<'a>(bytes: &'a [u8], language: Language) -> Lines<'a> {
+    Lines::new(bytes, get_language_config(&language), get_doc_markers(&language), get_string_syntax(&language))
+}
+
+// Classifies a template using both its own and its host language's comments.
+// Quotes in the text around the template tags do not start strings.
+pub fn classify_template<'a>(bytes: &'a [u8], template: Language, host: Option<Language>) -> Lines<'a> {
+    let cfg = get_template_config(&template, host);
+    Lines::new(bytes, cfg, get_doc_markers(&template), StringSyntax::default())
+}
+
+impl<'a> Lines<'a> {
+    pub(crate) fn new(bytes: &'a [u8],
+                      cfg: LineConfig<'a>,
+                      docs: &'a [&'a str],
+                      strings: StringSyntax) -> Lines<'a> {
+        let (singles, multis, verbatim) = match cfg {
+            LineConfig::Normal { single, multi } => {
+                (single.into_iter().collect(), multi.into_iter().collect(), None)
+            }
+            LineConfig::Everything { singles, multis } => (singles, multis, None),
+            LineConfig::Markup { multi, verbatim } => (vec![], vec![multi], Some(verbatim)),
+        };
+
+        Lines {
+            lines: ByteLines(bytes).lines(),
+            number: 0,
+            singles,
+            multis,
+            verbatim,
+            docs,
+            strings,
+            open: Open::Nothing,
+        }
+    }
+
+    fn is_doc(&self, rest: &[u8]) -> bool {
+        self.docs.iter().any(|doc| rest.starts_with(doc.as_bytes()))
+    }
+
+    fn open_marker(&self) -> Option<&'a str> {
+        match self.open {
+            Open::Nothing => None,
+            Open::Comment { end, .. } | Open::Verbatim(end) => Some(end),
+            Open::String(quote) => Some(quote),
+        }
+    }
+
+    // Length of a char literal such as 'a' or '\n' at the start of rest. A
+    // quote that doesn't start one is a Rust lifetime or a Haskell prime.
+    fn char_literal_len(&self, rest: &[u8]) -> Option<usize> {
+        if !self.strings.chars || rest[0] != b'\'' {
+            return None;
+        }
+        if rest.get(1) == Some(&b'\\') {
+            return rest.iter().skip(3).take(10).position(|&b| b == b'\'').map(|n| n + 4);
+        }
+        let width = match rest.get(1) {
+            Some(&b) if b < 0x80 => 1,
+            Some(&b) if b >= 0xf0 => 4,
+            Some(&b) if b >= 0xe0 => 3,
+            Some(_) => 2,
+            None => return None,
+        };
+        if rest.get(1 + width) == Some(&b'\'') {
+            Some(width + 2)
+        } else {
+            None
+        }
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Line<'a>;
+
+    fn next(&mut self) -> Option<Line<'a>> {
+        let text = self.lines.next()?;
+        self.number += 1;
+
+        let start = text.iter().position(|b| !b.is_ascii_whitespace());
+        let end = text.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(0, |n| n + 1);
+
+        let mut comments = vec![];
+        let mut strings = vec![];
+        let mut found_code = false;
+        let mut doc = false;
+
+        // Blank lines stay blank even inside a multiline comment or string
+        let mut pos = match start {
+            Some(start) => start,
+            None => {
+                if let Open::String(_) = self.open {
+                    strings.push(0..text.len());
+                }
+                return Some(Line {
+                    number: self.number,
+                    text,
+                    kind: LineKind::Blank,
+                    comments,
+                    strings,
+                    open: self.open_marker(),
+                });
+            }
+        };
+
+        let mut string_start = match self.open {
+            Open::String(_) => {
+                found_code = true;
+                Some(0)
+            }
+            _ => None,
+        };
+
+        let mut comment_start = match self.open {
+            Open::Comment { doc: open_doc, .. } => {
+                doc = open_doc;
+                Some(pos)
+            }
+            _ => None,
+        };
+
+        while pos < end {
+            let rest = &text[pos..end];
+            match self.open {
+                Open::Comment { end: marker, .. } => {
+                    if rest.starts_with(marker.as_bytes()) {
+                        pos += marker.len();
+                        comments.push(comment_start.take().unwrap_or(pos)..pos);
+                        self.open = Open::Nothing;
+                    } else {
+                        pos += 1;
+                    }
+                }
+                Open::Verbatim(marker) => {
+                    found_code = true;
+                    if rest.starts_with(marker.as_bytes()) {
+                        pos += marker.len();
+                        self.open = Open::Nothing;
+                    } else {
+                        pos += 1;
+                    }
+                }
+                Open::String(quote) => {
+                    if rest[0] == b'\\' && quote != "`" {
+                        pos += 2;
+                    } else if rest.starts_with(quote.as_bytes()) {
+                        strings.push(string_start.take().unwrap_or(pos)..pos);
+                        pos += quote.len();
+                        self.open = Open::Nothing;
+                    } else {
+                        pos += 1;
+                    }
+                }
+                Open::Nothing => {
+                    let first_comment = comments.is_empty() && !found_code;
+                    let multi = self.multis
+                        .iter()
+                        .find(|&&(multi_start, _)| rest.starts_with(multi_start.as_bytes()));
+                    let single = self.singles.iter().find(|single| rest.starts_with(single.as_bytes()));
+                    // A quote only starts a string when it is longer than any
+                    // comment marker in the same place, so Python's ''' stays a
+                    // comment
+                    let marker_len = multi.map_or(0, |&(multi_start, _)| multi_start.len())
+                        .max(single.map_or(0, |single| single.len()));
+                    let quote = self.strings.quotes
+                        .iter()
+                        .find(|quote| rest.starts_with(quote.as_bytes()))
+                        .filter(|quote| quote.len() > marker_len);
+
+                    if let Some(&quote) = quote {
+                        found_code = true;
+                        pos += quote.len();
+                        string_start = Some(pos);
+                        self.open = Open::String(quote);
+                    } else if let Some(&(multi_start, multi_end)) = multi {
+                        let is_doc = self.is_doc(rest);
+                        if first_comment {
+                            doc = is_doc;
+                        }
+                        comment_start = Some(pos);
+                        pos += multi_start.len();
+                        self.open = Open::Comment { end: multi_end, doc: is_doc };
+                    } else if single.is_some() {
+                        if first_comment {
+                            doc = self.is_doc(rest);
+                        }
+                        comments.push(pos..end);
+                        pos = end;
+                    } else if let Some((verbatim_start, verbatim_end)) = self.verbatim
+                        .filter(|&(verbatim_start, _)| rest.starts_with(verbatim_start.as_bytes()))
+                    {
+                        found_code = true;
+                        pos += verbatim_start.len();
+                        self.open = Open::Verbatim(verbatim_end);
+                    } else if let Some(len) = self.char_literal_len(rest) {
+                        found_code = true;
+                        strings.push(pos + 1..pos + len - 1);
+                        pos += len;
+                    } else {
+                        if !rest[0].is_ascii_whitespace() {
+                            found_code = true;
+                        }
+                        pos += 1;
+                    }
+                }
+            }
+        }
+
+        // The comment continues on the next line
+        if let Some(comment_start) = comment_start {
+            comments.push(comment_start..end);
+        }
+
+        // So does the string, unless its quotes only go to the end of the line
+        if let (Some(string_start), Open::String(quote)) = (string_start, self.open) {
+            strings.push(string_start..end.max(string_start));
+            if !self.strings.multiline.contains(&quote) {
+                self.open = Open::Nothing;
+            }
+        }
+
+        let kind = match (found_code, comments.is_empty()) {
+            (true, true) => LineKind::Code,
+            (true, false) => LineKind::Mixed,
+            (false, _) if doc => LineKind::Doc,
+            (false, _) => LineKind::Comment,
+        };
+
+        Some(Line {
+            number: self.number,
+            text,
+            kind,
+            comments,
+            strings,
+            open: self.open_marker(),
+        })
+    }
+}
+
+/// Adds up the line kinds of a file. Files that are not valid UTF-8 are not
+/// counted at all.
+pub fn tally<I: IntoIterator<Item = LineKind>>(bytes: &[u8], kinds: I) -> Count {
+    let mut count = Count::default();
+
+    if ::std::str::from_utf8(bytes).is_err() {
+        return count;
+    }
+
+    for kind in kinds {
+        count.add_line(kind);
+    }
+
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(source: &str, language: Language) -> Vec<LineKind> {
+        classify(source.as_bytes(), language).map(|line| line.kind).collect()
+    }
+
+    use self::LineKind::*;
+
+    #[test]
+    fn code_comment_and_blank_lines() {
+        let source = "fn main() {\n\n    // note\n    let x = 1;\n}\n";
+        assert_eq!(kinds(source, Language::Rust), vec![Code, Blank, Comment, Code, Code]);
+    }
+
+    #[test]
+    fn whitespace_only_lines_are_blank() {
+        assert_eq!(kinds("x = 1\n   \t\n", Language::Python), vec![Code, Blank]);
+    }
+
+    #[test]
+    fn code_next_to_a_comment_is_mixed() {
+        let source = "let x = 1; // one\n/* two */ let y = 2;\nlet z /* three */ = 3;\n";
+        assert_eq!(kinds(source, Language::Rust), vec![Mixed, Mixed, Mixed]);
+    }
+
+    #[test]
+    fn doc_comments() {
+        let source = "/// Docs\n//! Crate docs\n/** Block\n  docs */\n// plain\n";
+        assert_eq!(kinds(source, Language::Rust), vec![Doc, Doc, Doc, Doc, Comment]);
+    }
+
+    #[test]
+    fn doc_marker_only_counts_at_the_start_of_a_comment_line() {
+        // The line starts with a plain comment, so it stays a plain comment
+        assert_eq!(kinds("/* a */ /** b */\n", Language::Rust), vec![Comment]);
+    }
+
+    #[test]
+    fn multiline_comment_spans_lines_and_keeps_blank_lines_blank() {
+        let source = "/* start\n\n   middle\nend */ code();\ncode();\n";
+        assert_eq!(kinds(source, Language::C), vec![Comment, Blank, Comment, Mixed, Code]);
+    }
+
+    #[test]
+    fn block_opener_after_line_comment_is_ignored() {
+        // The /* is part of the line comment and opens nothing
+        let source = "// see /* here\ncode();\n";
+        assert_eq!(kinds(source, Language::C), vec![Comment, Code]);
+    }
+
+    #[test]
+    fn multiline_comments_do_not_nest() {
+        // The first */ closes the comment, whatever was opened inside it
+        let source = "/* outer /* inner */ code();\ncode(); */\n";
+        assert_eq!(kinds(source, Language::C), vec![Mixed, Code]);
+
+        let source = "{- outer {- inner -} code\n";
+        assert_eq!(kinds(source, Language::Haskell), vec![Mixed]);
+    }
+
+    #[test]
+    fn comment_markers_in_strings_are_code() {
+        let source = "let open = \"/**\";\nlet x = 1;\nlet url = \"http://x\"; // real\n";
+        assert_eq!(kinds(source, Language::Rust), vec![Code, Code, Mixed]);
+
+        let source = "char *c = \"/* not a comment\";\nint x;\n";
+        assert_eq!(kinds(source, Language::C), vec![Code, Code]);
+
+        let source = "x = \"# not a comment\"\ny = '#' # comment\n";
+        assert_eq!(kinds(source, Language::Python), vec![Code, Mixed]);
+    }
+
+    #[test]
+    fn escaped_quotes_do_not_end_strings() {
+        let source = "s = \"a \\\" // b\";\nc = '\"'; // d\n";
+        assert_eq!(kinds(source, Language::C), vec![Code, Mixed]);
+    }
+
+    #[test]
+    fn multiline_strings_hide_comment_markers() {
+        let source = "let s = \"first\n/* second\n\";\nx();\n";
+        let lines: Vec<Line> = classify(source.as_bytes(), Language::Rust).collect();
+        let line_kinds: Vec<LineKind> = lines.iter().map(|line| line.kind).collect();
+        assert_eq!(line_kinds, vec![Code, Code, Code, Code]);
+        assert_eq!(lines[1].strings, vec![0..9]);
+        assert_eq!(lines[3].open, None);
+
+        // Strings in C end with their line
+        let source = "s = \"open\n/* comment */\n";
+        assert_eq!(kinds(source, Language::C), vec![Code, Comment]);
+    }
+
+    #[test]
+    fn triple_quote_comments_win_over_quotes() {
+        let source = "'''\ndoc\n'''\n\"\"\"\n# text\n\"\"\"\n";
+        assert_eq!(kinds(source, Language::Python), vec![Doc, Doc, Doc, Code, Code, Code]);
+    }
+
+    #[test]
+    fn string_ranges_exclude_quotes() {
+        let line = classify(b"f(\"ab\", 'c', x');", Language::Rust).next().unwrap();
+        assert_eq!(line.strings, vec![3..5, 9..10]);
+    }
+
+    #[test]
+    fn open_marker_is_reported_after_each_line() {
+        let lines: Vec<Option<&str>> = classify(b"/* a\nb */\n", Language::C)
+            .map(|line| line.open)
+            .collect();
+        assert_eq!(lines, vec![Some("*/"), None]);
+    }
+
+    #[test]
+    fn comment_ranges_include_markers() {
+        let line = classify(b"x(); // hi", Language::C).next().unwrap();
+        assert_eq!(line.comments, vec![5..10]);
+    }
+
+    #[test]
+    fn tally_counts_each_kind() {
+        let source = b"int x;\n\n/* c */\n/** d */\nint y; // m\n";
+        let count = tally(source, classify(source, Language::C).map(|line| line.kind));
+        assert_eq!((count.code, count.comments, count.blank, count.total), (2, 2, 1, 5));
+    }
+
+    #[test]
+    fn tally_counts_nothing_for_invalid_utf8() {
+        let source = b"int x;\n\xff\xfe\n";
+        let count = tally(source, classify(source, Language::C).map(|line| line.kind));
+        assert_eq!((count.code, count.comments, count.blank, count.total), (0, 0, 0, 0));
+    }
+}
@@ -0,0 +1,47 @@
+use {classify, Language, Line, Lines};
+
+// A line with its comments removed and the contents of its string and char
+// literals dropped, leaving only the quotes, so that keywords and operators
+// can be looked for without false matches
+pub struct CodeLine<'a> {
+    pub line: Line<'a>,
+    pub code: Vec<u8>,
+    // The line starts inside a string opened on an earlier line
+    pub continued: bool,
+}
+
+pub struct CodeLines<'a> {
+    lines: Lines<'a>,
+}
+
+pub fn code_lines<'a>(bytes: &'a [u8], language: Language) -> CodeLines<'a> {
+    CodeLines {
+        lines: classify(bytes, language),
+    }
+}
+
+impl<'a> Iterator for CodeLines<'a> {
+    type Item = CodeLine<'a>;
+
+    fn next(&mut self) -> Option<CodeLine<'a>> {
+        let line = self.lines.next()?;
+        let text = line.text;
+        let mut code = Vec::with_capacity(text.len());
+        let continued = line.strings.first().is_some_and(|string| string.start == 0);
+
+        let mut pos = 0;
+        while pos < text.len() {
+            if let Some(comment) = line.comments.iter().find(|comment| comment.start == pos) {
+                code.push(b' ');
+                pos = comment.end.max(pos + 1);
+            } else if let Some(string) = line.strings.iter().find(|string| string.start == pos && string.end > pos) {
+                pos = string.end;
+            } else {
+                code.push(text[pos]);
+                pos += 1;
+            }
+        }
+
+        Some(CodeLine { line, code, continued })
+    }
+}
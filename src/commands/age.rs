@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cl::*;
+
+use super::blame_files;
+
+const AGE_BUCKETS: [(&str, i64); 5] = [
+    ("< 1 month", 30),
+    ("< 6 months", 182),
+    ("< 1 year", 365),
+    ("< 3 years", 3 * 365),
+    ("Older", i64::MAX),
+];
+
+// Lines of code per age bucket for one language, with the split per file
+#[derive(Default)]
+struct LanguageAge {
+    lines: [u32; 5],
+    files: BTreeMap<String, [u32; 5]>,
+}
+
+pub fn run(targets: &[&str], by_file: bool) -> io::Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or(0);
+
+    let mut by_language: BTreeMap<Language, LanguageAge> = BTreeMap::new();
+    blame_files(targets, |path, language, line, blame| {
+        if line.kind != LineKind::Code && line.kind != LineKind::Mixed {
+            return;
+        }
+        let days = (now - blame.time) / (24 * 60 * 60);
+        let bucket = AGE_BUCKETS.iter().position(|&(_, limit)| days < limit).unwrap_or(4);
+        let age = by_language.entry(language).or_default();
+        age.lines[bucket] += 1;
+        if by_file {
+            age.files.entry(String::from(path)).or_default()[bucket] += 1;
+        }
+    })?;
+
+    let linesep = "-".repeat(80);
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >11} {2: >11} {3: >11} {4: >11} {5: >11}",
+             "Language",
+             AGE_BUCKETS[0].0,
+             AGE_BUCKETS[1].0,
+             AGE_BUCKETS[2].0,
+             AGE_BUCKETS[3].0,
+             AGE_BUCKETS[4].0);
+    println!("{}", linesep);
+
+    let mut totals = [0; 5];
+    for (language, age) in by_language {
+        let buckets = age.lines;
+        if by_file {
+            println!("{}", linesep);
+        }
+        println!(" {0: <17} {1: >11} {2: >11} {3: >11} {4: >11} {5: >11}",
+                 language,
+                 buckets[0],
+                 buckets[1],
+                 buckets[2],
+                 buckets[3],
+                 buckets[4]);
+        if by_file {
+            println!("{}", linesep);
+        }
+        for (path, buckets) in age.files {
+            println!("|{0: <17} {1: >11} {2: >11} {3: >11} {4: >11} {5: >11}",
+                     path,
+                     buckets[0],
+                     buckets[1],
+                     buckets[2],
+                     buckets[3],
+                     buckets[4]);
+        }
+        for (total, lines) in totals.iter_mut().zip(buckets.iter()) {
+            *total += lines;
+        }
+    }
+
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >11} {2: >11} {3: >11} {4: >11} {5: >11}",
+             "Total",
+             totals[0],
+             totals[1],
+             totals[2],
+             totals[3],
+             totals[4]);
+    println!("{}", linesep);
+    Ok(())
+}
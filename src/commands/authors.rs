@@ -0,0 +1,77 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use cl::*;
+
+use super::blame_files;
+
+// Lines one author owns in one language, and the files they appear in
+#[derive(Default)]
+struct AuthorTotal {
+    files: u32,
+    count: Count,
+}
+
+pub fn run(targets: &[&str]) -> io::Result<()> {
+    let mut by_author: BTreeMap<(String, String), BTreeMap<Language, AuthorTotal>> = BTreeMap::new();
+    let mut last_path = String::new();
+    let mut seen = vec![];
+    blame_files(targets, |path, language, line, blame| {
+        if path != last_path {
+            last_path = String::from(path);
+            seen.clear();
+        }
+        let author = (blame.author.clone(), blame.email.clone());
+        let first = !seen.contains(&(author.clone(), language));
+        if first {
+            seen.push((author.clone(), language));
+        }
+        let total = by_author
+            .entry(author)
+            .or_default()
+            .entry(language)
+            .or_default();
+        if first {
+            total.files += 1;
+        }
+        total.count.add_line(line.kind);
+    })?;
+
+    let linesep = "-".repeat(80);
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
+             "Author",
+             "Files",
+             "Lines",
+             "Blank",
+             "Comments",
+             "Code");
+    println!("{}", linesep);
+
+    let mut totals = Count::default();
+    for ((author, email), by_language) in by_author {
+        println!(" {} <{}>", author, email);
+        for (language, total) in by_language {
+            let count = &total.count;
+            println!("   {0: <15} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
+                     language,
+                     total.files,
+                     count.total,
+                     count.blank,
+                     count.comments,
+                     count.code);
+            totals.merge(count);
+        }
+    }
+
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
+             "Total",
+             "",
+             totals.total,
+             totals.blank,
+             totals.comments,
+             totals.code);
+    println!("{}", linesep);
+    Ok(())
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ignore::WalkBuilder;
+
+use cl::*;
+use cl::git;
+
+// Files of a directory, or of the current directory in a git revision, by
+// path relative to it
+fn collect_tree(source: &str) -> io::Result<BTreeMap<String,Vec<u8>>> {
+    let mut files = BTreeMap::new();
+
+    if Path::new(source).is_dir() {
+        for entry in WalkBuilder::new(source).build() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            if !entry.file_type().is_some_and(|ft| ft.is_file()) {
+                continue;
+            }
+            let path = entry.path()
+                .strip_prefix(source)
+                .unwrap_or_else(|_| entry.path())
+                .to_string_lossy()
+                .into_owned();
+            if get_language(&path) != Language::Unknown {
+                files.insert(path, fs::read(entry.path())?);
+            }
+        }
+        return Ok(files);
+    }
+
+    let prefix = git::git_string(".", &["rev-parse", "--show-prefix"])?;
+    let mut blobs = git::BlobReader::new(".")?;
+    for entry in git::ls_tree(".", source)? {
+        if entry.path.starts_with(&*prefix) && get_language(&entry.path) != Language::Unknown {
+            let bytes = blobs.read(&entry.oid)?;
+            files.insert(String::from(&entry.path[prefix.len()..]), bytes);
+        }
+    }
+    Ok(files)
+}
+
+#[derive(Default)]
+struct DiffFiles {
+    same: u32,
+    modified: u32,
+    added: u32,
+    removed: u32,
+}
+
+// The changes in one language, with the changes of each file when they are
+// shown
+#[derive(Default)]
+struct LanguageDiff<'a> {
+    files: DiffFiles,
+    diff: DiffCount,
+    file_diffs: Vec<(&'a str, DiffCount)>,
+}
+
+pub fn run(old: &str, new: &str, by_file: bool) {
+    let (old_files, new_files) = match (collect_tree(old), collect_tree(new)) {
+        (Ok(old_files), Ok(new_files)) => (old_files, new_files),
+        (Err(err), _) | (_, Err(err)) => {
+            println!("Error: {}", err);
+            return;
+        }
+    };
+
+    let mut paths: Vec<&String> = old_files.keys().chain(new_files.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut by_language: BTreeMap<String,LanguageDiff> = BTreeMap::new();
+    for path in paths {
+        let old_bytes = old_files.get(path);
+        let new_bytes = new_files.get(path);
+        if old_bytes.into_iter().chain(new_bytes).any(|bytes| looks_binary(bytes)) {
+            continue;
+        }
+
+        // Each language of a literate source is compared on its own
+        let language = get_language(path);
+        let old_parts = old_bytes.map_or(vec![], |bytes| file_parts(path, language, bytes));
+        let new_parts = new_bytes.map_or(vec![], |bytes| file_parts(path, language, bytes));
+        let mut languages: Vec<Language> = old_parts
+            .iter()
+            .chain(&new_parts)
+            .map(|part| part.language)
+            .collect();
+        languages.sort();
+        languages.dedup();
+
+        for language in languages {
+            let old_part = old_parts.iter().find(|part| part.language == language);
+            let new_part = new_parts.iter().find(|part| part.language == language);
+            let mut diff = DiffCount::default();
+            let entry = by_language
+                .entry(language.to_string().to_owned())
+                .or_default();
+            match (old_part, new_part) {
+                (Some(old_part), Some(new_part)) => {
+                    diff = diff_lines(old_part.lines(), new_part.lines());
+                    if diff.is_unchanged() {
+                        entry.files.same += 1;
+                    } else {
+                        entry.files.modified += 1;
+                    }
+                }
+                (Some(old_part), None) => {
+                    diff.removed = old_part.count();
+                    entry.files.removed += 1;
+                }
+                (None, Some(new_part)) => {
+                    diff.added = new_part.count();
+                    entry.files.added += 1;
+                }
+                (None, None) => continue,
+            }
+            entry.diff.merge(&diff);
+            if by_file && !diff.is_unchanged() {
+                entry.file_diffs.push((path, diff));
+            }
+        }
+    }
+
+    let linesep = "-".repeat(80);
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12}",
+             "Language",
+             "Files",
+             "Blank",
+             "Comments",
+             "Code");
+    println!("{}", linesep);
+
+    let mut total_files = DiffFiles::default();
+    let mut total = DiffCount::default();
+    for (language, language_diff) in by_language {
+        let files = language_diff.files;
+        print_diff(&language, &files, &language_diff.diff);
+        for (path, file_diff) in language_diff.file_diffs {
+            print_file_diff(path, &file_diff);
+        }
+        total_files.same += files.same;
+        total_files.modified += files.modified;
+        total_files.added += files.added;
+        total_files.removed += files.removed;
+        total.merge(&language_diff.diff);
+    }
+
+    println!("{}", linesep);
+    print_diff("Total", &total_files, &total);
+    println!("{}", linesep);
+}
+
+fn print_diff(language: &str, files: &DiffFiles, diff: &DiffCount) {
+    println!(" {}", language);
+    let rows = [
+        ("same", files.same, &diff.same),
+        ("modified", files.modified, &diff.modified),
+        ("added", files.added, &diff.added),
+        ("removed", files.removed, &diff.removed),
+    ];
+    for &(name, files, count) in rows.iter() {
+        println!("   {0: <15} {1: >8} {2: >12} {3: >12} {4: >12}",
+                 name,
+                 files,
+                 count.blank,
+                 count.comments,
+                 count.code);
+    }
+}
+
+fn print_file_diff(path: &str, diff: &DiffCount) {
+    println!("|{}", path);
+    let rows = [
+        ("modified", &diff.modified),
+        ("added", &diff.added),
+        ("removed", &diff.removed),
+    ];
+    for &(name, count) in rows.iter() {
+        println!("|  {0: <24} {1: >12} {2: >12} {3: >12}",
+                 name,
+                 count.blank,
+                 count.comments,
+                 count.code);
+    }
+}
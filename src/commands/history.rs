@@ -0,0 +1,124 @@
+use std::collections::{BTreeMap, HashMap};
+use std::collections::hash_map::Entry;
+use std::io;
+use std::str::FromStr;
+
+use serde_json;
+
+use cl::*;
+use cl::git;
+
+// How commits are sampled by cl history
+pub enum Step {
+    Commits(usize),
+    Weekly,
+}
+
+impl FromStr for Step {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Step,Self::Err> {
+        match s {
+            "weekly" | "Weekly" => Ok(Step::Weekly),
+            _ => match s.parse() {
+                Ok(n) if n > 0 => Ok(Step::Commits(n)),
+                _ => Err(()),
+            },
+        }
+    }
+}
+
+// Picks the commits to count: every Nth commit, or the last commit of each
+// week. The most recent commit is always included.
+fn sample_commits<'a>(commits: &'a [git::Commit], step: &Step) -> Vec<&'a git::Commit> {
+    let week = 7 * 24 * 60 * 60;
+    let mut samples: Vec<&git::Commit> = vec![];
+    for (i, commit) in commits.iter().enumerate() {
+        let last = i + 1 == commits.len();
+        let take = match *step {
+            Step::Commits(n) => i % n == 0 || last,
+            Step::Weekly => last || commits[i + 1].time / week != commit.time / week,
+        };
+        if take {
+            samples.push(commit);
+        }
+    }
+    samples
+}
+
+pub fn run(since: Option<&str>, step: &Step, json: bool) -> io::Result<()> {
+    let prefix = git::git_string(".", &["rev-parse", "--show-prefix"])?;
+    let commits = git::log(".", since)?;
+    let mut blobs = git::BlobReader::new(".")?;
+
+    // Blobs are only counted the first time they are seen
+    let mut cache: HashMap<(String, Language), Vec<(Language, Count)>> = HashMap::new();
+    let mut samples = vec![];
+    for commit in sample_commits(&commits, step) {
+        let mut by_language: BTreeMap<Language, (u32, Count)> = BTreeMap::new();
+        for entry in git::ls_tree(".", &commit.oid)? {
+            let language = get_language(&entry.path);
+            if !entry.path.starts_with(&*prefix) || language == Language::Unknown {
+                continue;
+            }
+            let counts = match cache.entry((entry.oid, language)) {
+                Entry::Occupied(cached) => cached.get().clone(),
+                Entry::Vacant(vacant) => {
+                    let bytes = blobs.read(&vacant.key().0)?;
+                    let counts = if looks_binary(&bytes) {
+                        vec![]
+                    } else {
+                        file_parts(&entry.path, language, &bytes)
+                            .iter()
+                            .map(|part| (part.language, part.count()))
+                            .collect()
+                    };
+                    vacant.insert(counts).clone()
+                }
+            };
+            for (language, count) in counts {
+                let totals = by_language.entry(language).or_default();
+                totals.0 += 1;
+                totals.1.merge(&count);
+            }
+        }
+        samples.push((commit, by_language));
+    }
+
+    if json {
+        let samples: Vec<serde_json::Value> = samples
+            .iter()
+            .map(|&(commit, ref by_language)| {
+                let languages: serde_json::Map<String, serde_json::Value> = by_language
+                    .iter()
+                    .map(|(language, &(files, ref count))| {
+                        (language.to_string().to_owned(), json!({
+                            "files": files,
+                            "code": count.code,
+                            "comments": count.comments,
+                            "blank": count.blank,
+                            "total": count.total,
+                        }))
+                    })
+                    .collect();
+                json!({ "commit": commit.oid, "date": commit.date, "languages": languages })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&samples).unwrap());
+    } else {
+        println!("commit,date,language,files,code,comments,blank,total");
+        for (commit, by_language) in samples {
+            for (language, (files, count)) in by_language {
+                println!("{},{},{},{},{},{},{},{}",
+                         commit.oid,
+                         commit.date,
+                         language,
+                         files,
+                         count.code,
+                         count.comments,
+                         count.blank,
+                         count.total);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::io;
+
+use serde_json;
+
+use cl::*;
+use cl::git;
+
+struct Hotspot {
+    path: String,
+    language: Language,
+    count: Count,
+    commits: u32,
+    score: f64,
+}
+
+// Scores tracked files by their code lines and the number of commits that
+// changed them since a date. Both are scaled against the largest value and
+// raised to their weight, so a file needs to be big and busy to rank high.
+pub fn run(targets: &[&str], since: &str, weights: [f64; 2], limit: usize, json: bool) -> io::Result<()> {
+    let changes = git::changes(".", since, targets)?;
+
+    let mut hotspots = vec![];
+    for path in git::ls_files(".", targets)? {
+        let language = get_language(&path);
+        if language == Language::Unknown || is_binary(&path) {
+            continue;
+        }
+        let commits = changes.get(&path).cloned().unwrap_or(0);
+        hotspots.push(Hotspot {
+            count: count(&path),
+            path,
+            language,
+            commits,
+            score: 0.0,
+        });
+    }
+
+    let max_code = hotspots.iter().map(|hotspot| hotspot.count.code).max().unwrap_or(0).max(1);
+    let max_commits = hotspots.iter().map(|hotspot| hotspot.commits).max().unwrap_or(0).max(1);
+    for hotspot in &mut hotspots {
+        let size = f64::from(hotspot.count.code) / f64::from(max_code);
+        let churn = f64::from(hotspot.commits) / f64::from(max_commits);
+        hotspot.score = size.powf(weights[0]) * churn.powf(weights[1]);
+    }
+    hotspots.sort_by(|h1, h2| {
+        h2.score.partial_cmp(&h1.score).unwrap().then_with(|| h1.path.cmp(&h2.path))
+    });
+    hotspots.truncate(limit);
+
+    if json {
+        let hotspots: Vec<serde_json::Value> = hotspots
+            .iter()
+            .map(|hotspot| json!({
+                "path": hotspot.path,
+                "language": hotspot.language.to_string(),
+                "commits": hotspot.commits,
+                "code": hotspot.count.code,
+                "comments": hotspot.count.comments,
+                "blank": hotspot.count.blank,
+                "total": hotspot.count.total,
+                "score": hotspot.score,
+            }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&hotspots).unwrap());
+        return Ok(());
+    }
+
+    let linesep = "-".repeat(80);
+    println!("{}", linesep);
+    println!(" {0: <38} {1: >12} {2: >12} {3: >12}",
+             "File",
+             "Commits",
+             "Code",
+             "Score");
+    println!("{}", linesep);
+    for hotspot in hotspots {
+        println!(" {0: <38} {1: >12} {2: >12} {3: >12.3}",
+                 hotspot.path,
+                 hotspot.commits,
+                 hotspot.count.code,
+                 hotspot.score);
+    }
+    println!("{}", linesep);
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::fs;
+use std::io;
+
+use cl::*;
+use cl::git;
+
+pub mod age;
+pub mod authors;
+pub mod diff;
+pub mod history;
+pub mod hotspots;
+
+// Classifies each line of the tracked files under targets and passes it
+// along with the blame of the line
+fn blame_files<F>(targets: &[&str], mut visit: F) -> io::Result<()>
+    where F: FnMut(&str, Language, &Line, &git::BlameLine)
+{
+    for path in git::ls_files(".", targets)? {
+        let language = get_language(&path);
+        if language == Language::Unknown {
+            continue;
+        }
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(_) => continue,
+        };
+        if looks_binary(&bytes) || ::std::str::from_utf8(&bytes).is_err() {
+            continue;
+        }
+        let blame = git::blame(".", &path)?;
+        for part in file_parts(&path, language, &bytes) {
+            for line in part.lines() {
+                let number = match part.line_number(&line) {
+                    Some(number) => number,
+                    None => continue,
+                };
+                if let Some(blame_line) = blame.get(number as usize - 1) {
+                    visit(&path, part.language, &line, blame_line);
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use std::str;
+
+use code::code_lines;
+use tokens::count_tokens;
+use {get_decision_points, get_plain_operators, Language};
+
+// Estimates cyclomatic complexity as the number of decision points in the
+// code: branches, loops, case arms, exception handlers and short circuiting
+// operators. Returns None for languages without known decision points.
+pub fn count_complexity_bytes(bytes: &[u8], language: Language) -> Option<u32> {
+    let points = get_decision_points(&language);
+    let plain = get_plain_operators(&language);
+    if points.is_empty() || str::from_utf8(bytes).is_err() {
+        return None;
+    }
+
+    Some(code_lines(bytes, language)
+        .map(|line| count_tokens(&line.code, points, plain))
+        .sum())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn branches_loops_and_short_circuits() {
+        let source = b"if (a && b) {\n    for (;;) {}\n} // if || while\nx = \"if ? ||\";\n";
+        assert_eq!(count_complexity_bytes(source, Language::C), Some(3));
+        assert_eq!(count_complexity_bytes(b"if a or b:\n    pass\n", Language::Python), Some(2));
+        assert_eq!(count_complexity_bytes(b"no branches\n", Language::Markdown), None);
+    }
+
+    #[test]
+    fn javascript_optional_chaining_is_not_a_branch() {
+        assert_eq!(count_complexity_bytes(b"let a = b?.c ?? d;\n", Language::Javascript), Some(1));
+        assert_eq!(count_complexity_bytes(b"let a = b ? c : d;\n", Language::Javascript), Some(1));
+    }
+}
@@ -0,0 +1,310 @@
+use std::str;
+
+use code::code_lines;
+use tokens::{find_word, word_len};
+use {get_definition_style, DefinitionStyle, Language, LineKind};
+
+#[derive(PartialEq, Default, Clone)]
+pub struct Definitions {
+    pub functions: u32,
+    pub types: u32,
+    pub impls: u32,
+    // Code lines of all functions, from the signature to the end of the body
+    pub function_lines: u32,
+}
+
+impl Definitions {
+    pub fn merge(&mut self, definitions2: &Definitions) {
+        self.functions += definitions2.functions;
+        self.types += definitions2.types;
+        self.impls += definitions2.impls;
+        self.function_lines += definitions2.function_lines;
+    }
+
+    pub fn average_function_lines(&self) -> f64 {
+        if self.functions == 0 {
+            0.0
+        } else {
+            f64::from(self.function_lines) / f64::from(self.functions)
+        }
+    }
+}
+
+// Words that start a block with a parameter list that is not a function
+const CONTROL_KEYWORDS: &[&str] = &[
+    "if", "else", "for", "while", "do", "switch", "case", "try", "catch", "return",
+    "synchronized", "new", "sizeof",
+];
+// Words that start a block that may hold functions, besides types
+const NAMESPACE_KEYWORDS: &[&str] = &["namespace", "extern"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum Block {
+    // Number of code lines before the function started
+    Function(u32),
+    Type,
+    Impl,
+    Namespace,
+    Other,
+}
+
+fn starts_with_word(text: &[u8], word: &str) -> bool {
+    find_word(text, word) == Some(0)
+}
+
+// Code since the end of the previous statement or block
+struct Statement {
+    text: Vec<u8>,
+    // Offset in text where each line starts, with the number of code lines before it
+    lines: Vec<(usize, u32)>,
+    parens: i32,
+}
+
+// Whether a statement is inside a function body
+fn in_function(blocks: &[Block]) -> bool {
+    blocks.iter().any(|block| matches!(*block, Block::Function(_)))
+}
+
+impl Statement {
+    fn new(code_before: u32) -> Statement {
+        Statement {
+            text: vec![],
+            lines: vec![(0, code_before)],
+            parens: 0,
+        }
+    }
+
+    fn code_before(&self, offset: usize) -> u32 {
+        self.lines
+            .iter()
+            .rev()
+            .find(|&&(start, _)| start <= offset)
+            .map_or(0, |&(_, code_before)| code_before)
+    }
+
+    fn first_word(&self) -> &[u8] {
+        let start = self.text.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(0);
+        let rest = &self.text[start..];
+        &rest[..word_len(rest)]
+    }
+
+    fn has_type_keyword(&self, types: &[&str]) -> bool {
+        types.iter().any(|keyword| find_word(&self.text, keyword).is_some())
+    }
+
+    // Unit and tuple structs end with a semicolon instead of a body
+    fn is_type_without_body(&self, style: &DefinitionStyle, blocks: &[Block]) -> bool {
+        match *style {
+            DefinitionStyle::Keyword { types, .. } => self.has_type_keyword(types) && !in_function(blocks),
+            _ => false,
+        }
+    }
+
+    // The function is only recognised where functions can be defined, so
+    // blocks inside function bodies don't count
+    fn looks_like_function(&self, blocks: &[Block]) -> bool {
+        let first_word = self.first_word();
+        let paren = match self.text.iter().position(|&b| b == b'(') {
+            Some(paren) => paren,
+            None => return false,
+        };
+        blocks.iter().all(|&block| block == Block::Type || block == Block::Namespace)
+            && !CONTROL_KEYWORDS.iter().any(|keyword| keyword.as_bytes() == first_word)
+            && !self.text[..paren].contains(&b'=')
+    }
+
+    // Decides what the block opened after the statement is
+    fn block(&self, style: &DefinitionStyle, blocks: &[Block]) -> Block {
+        let start = self.text.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(0);
+        match *style {
+            DefinitionStyle::Keyword { functions, types, impls } => {
+                let function = functions.iter().filter_map(|keyword| find_word(&self.text, keyword)).min();
+                if let Some(offset) = function {
+                    Block::Function(self.code_before(offset))
+                } else if impls.iter().any(|keyword| find_word(&self.text, keyword).is_some()) {
+                    Block::Impl
+                } else if self.has_type_keyword(types) && !in_function(blocks) {
+                    // Inside a function body the keyword is an anonymous type,
+                    // as in Go's make(chan struct{})
+                    Block::Type
+                } else if blocks.last() == Some(&Block::Type) && self.looks_like_function(&blocks[blocks.len() - 1..]) {
+                    // A method of a class
+                    Block::Function(self.code_before(start))
+                } else {
+                    Block::Other
+                }
+            }
+            DefinitionStyle::Signature { types } => {
+                if NAMESPACE_KEYWORDS.iter().any(|keyword| keyword.as_bytes() == self.first_word()) {
+                    Block::Namespace
+                } else if self.looks_like_function(blocks) {
+                    Block::Function(self.code_before(start))
+                } else if !self.text.contains(&b'(') && !self.text.contains(&b'=') && self.has_type_keyword(types) {
+                    Block::Type
+                } else {
+                    Block::Other
+                }
+            }
+            DefinitionStyle::Indented { .. } => Block::Other,
+        }
+    }
+}
+
+fn is_code(kind: LineKind) -> bool {
+    kind == LineKind::Code || kind == LineKind::Mixed
+}
+
+// Definitions with a body in braces, found by following the statements and
+// blocks of the code
+fn count_braced(bytes: &[u8], language: Language, style: &DefinitionStyle) -> Definitions {
+    let mut definitions = Definitions::default();
+    let mut blocks = vec![];
+    let mut statement = Statement::new(0);
+    let mut code_seen = 0;
+    let preprocessor = matches!(*style, DefinitionStyle::Signature { .. });
+
+    for line in code_lines(bytes, language).filter(|line| is_code(line.line.kind)) {
+        code_seen += 1;
+        let code = &line.code;
+        if preprocessor && code.iter().find(|b| !b.is_ascii_whitespace()) == Some(&b'#') {
+            continue;
+        }
+
+        let offset = statement.text.len();
+        statement.lines.push((offset, code_seen - 1));
+        for &b in code {
+            match b {
+                b';' if statement.parens <= 0 => {
+                    if statement.is_type_without_body(style, &blocks) {
+                        definitions.types += 1;
+                    }
+                    statement = Statement::new(code_seen - 1);
+                }
+                b'{' => {
+                    let block = statement.block(style, &blocks);
+                    match block {
+                        Block::Type => definitions.types += 1,
+                        Block::Impl => definitions.impls += 1,
+                        _ => (),
+                    }
+                    blocks.push(block);
+                    statement = Statement::new(code_seen - 1);
+                }
+                b'}' => {
+                    if let Some(Block::Function(start)) = blocks.pop() {
+                        definitions.functions += 1;
+                        definitions.function_lines += code_seen - start;
+                    }
+                    statement = Statement::new(code_seen - 1);
+                }
+                _ => {
+                    match b {
+                        b'(' => statement.parens += 1,
+                        b')' => statement.parens -= 1,
+                        _ => (),
+                    }
+                    statement.text.push(b);
+                }
+            }
+        }
+        statement.text.push(b' ');
+    }
+
+    definitions
+}
+
+// Definitions whose body is indented below them, ending at the next line
+// that is indented as much as the definition or less
+fn count_indented(bytes: &[u8], language: Language, functions: &[&str], types: &[&str]) -> Definitions {
+    let mut definitions = Definitions::default();
+    // Indentation of each enclosing function and the number of code lines before it
+    let mut open: Vec<(usize, u32)> = vec![];
+    let mut code_seen = 0;
+
+    for line in code_lines(bytes, language).filter(|line| is_code(line.line.kind)) {
+        if !line.continued {
+            let indent = line.code.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(0);
+            while open.last().is_some_and(|&(open_indent, _)| open_indent >= indent) {
+                let (_, start) = open.pop().unwrap();
+                definitions.functions += 1;
+                definitions.function_lines += code_seen - start;
+            }
+
+            let code = &line.code[indent..];
+            if functions.iter().any(|keyword| starts_with_word(code, keyword)) {
+                open.push((indent, code_seen));
+            } else if types.iter().any(|keyword| starts_with_word(code, keyword)) {
+                definitions.types += 1;
+            }
+        }
+        code_seen += 1;
+    }
+
+    for (_, start) in open {
+        definitions.functions += 1;
+        definitions.function_lines += code_seen - start;
+    }
+    definitions
+}
+
+// Counts function, method and type definitions with simple per language
+// recognisers. Returns None for languages without one.
+pub fn count_definitions_bytes(bytes: &[u8], language: Language) -> Option<Definitions> {
+    let style = get_definition_style(&language)?;
+    if str::from_utf8(bytes).is_err() {
+        return None;
+    }
+
+    Some(match style {
+        DefinitionStyle::Indented { functions, types } => {
+            count_indented(bytes, language, functions, types)
+        }
+        _ => count_braced(bytes, language, &style),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust(source: &str) -> Definitions {
+        count_definitions_bytes(source.as_bytes(), Language::Rust).unwrap()
+    }
+
+    #[test]
+    fn unit_and_tuple_structs_are_types() {
+        let definitions = rust("struct Unit;\npub struct Pair(u32, u32);\npub(crate) struct Id(pub u64);\n");
+        assert_eq!((definitions.types, definitions.functions), (3, 0));
+    }
+
+    #[test]
+    fn impl_blocks_and_their_methods() {
+        let source = "struct Point { x: i32 }\n\
+                      impl Point {\n    fn new() -> Point {\n        Point { x: 0 }\n    }\n}\n\
+                      impl<T> Trait for Vec<T> where T: Copy {\n}\n\
+                      fn iter() -> impl Iterator<Item = u32> {\n    0..1\n}\n";
+        let definitions = rust(source);
+        assert_eq!((definitions.types, definitions.impls, definitions.functions), (1, 2, 2));
+    }
+
+    #[test]
+    fn statements_in_functions_are_not_types() {
+        let definitions = rust("fn f() {\n    let s = \"struct X;\";\n    g(s);\n}\n");
+        assert_eq!((definitions.types, definitions.functions, definitions.function_lines), (0, 1, 4));
+    }
+
+    #[test]
+    fn go_anonymous_structs_in_functions_are_not_types() {
+        let source = "package main\n\n\
+                      type Set map[string]struct{}\n\n\
+                      func main() {\n\tdone := make(chan struct{})\n\tseen := map[string]struct{}{}\n\t_ = seen\n\t<-done\n}\n";
+        let definitions = count_definitions_bytes(source.as_bytes(), Language::Go).unwrap();
+        assert_eq!((definitions.types, definitions.functions), (1, 1));
+    }
+
+    #[test]
+    fn c_forward_declarations_are_not_types() {
+        let definitions = count_definitions_bytes(b"struct node;\nstruct list { int n; };\n", Language::C).unwrap();
+        assert_eq!(definitions.types, 1);
+    }
+}
@@ -0,0 +1,282 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use globset::{self, Glob, GlobSet, GlobSetBuilder};
+
+use {get_language, map_file, Language};
+
+// Only the start of a file is searched for generated-code banners
+const HEADER_LEN: usize = 1024;
+
+const GENERATED_MARKERS: &[&str] = &[
+    "@generated",
+    "DO NOT EDIT",
+    "Generated by the protocol buffer compiler",
+    "generated by protoc-gen-",
+    "automatically generated by rust-bindgen",
+    "This file is automatically generated",
+    "Autogenerated by Thrift",
+];
+
+const GENERATED_SUFFIXES: &[&str] = &[
+    ".pb.go",
+    ".pb.cc",
+    ".pb.h",
+    ".pb.rs",
+    "_pb2.py",
+    "_pb2_grpc.py",
+    "_generated.rs",
+    "_generated.go",
+    ".generated.cs",
+    ".designer.cs",
+];
+
+const LOCKFILES: &[&str] = &[
+    "cargo.lock",
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "gemfile.lock",
+    "poetry.lock",
+    "pipfile.lock",
+    "composer.lock",
+    "go.sum",
+];
+
+pub fn is_generated_name(filepath: &str) -> bool {
+    let filename = match Path::new(filepath).file_name().and_then(|name| name.to_str()) {
+        Some(filename) => filename.to_lowercase(),
+        None => return false,
+    };
+
+    LOCKFILES.contains(&&*filename)
+        || GENERATED_SUFFIXES.iter().any(|suffix| filename.ends_with(&*suffix.to_lowercase()))
+}
+
+pub fn has_generated_header(bytes: &[u8]) -> bool {
+    let header = String::from_utf8_lossy(&bytes[..bytes.len().min(HEADER_LEN)]);
+    GENERATED_MARKERS.iter().any(|marker| header.contains(marker))
+}
+
+pub fn is_generated(filepath: &str) -> bool {
+    if is_generated_name(filepath) {
+        return true;
+    }
+    match map_file(filepath) {
+        Some(mmap) => has_generated_header(&mmap),
+        None => false,
+    }
+}
+
+// Minified sources have few, very long lines
+const MINIFIED_AVERAGE_LINE: usize = 250;
+const MINIFIED_LONGEST_LINE: usize = 32 * 1024;
+
+pub fn is_minified_name(filepath: &str) -> bool {
+    let path = Path::new(filepath);
+    let filename = match path.file_name().and_then(|name| name.to_str()) {
+        Some(filename) => filename.to_lowercase(),
+        None => return false,
+    };
+
+    if filename.contains(".min.") || filename.contains(".bundle.") {
+        return true;
+    }
+
+    let bundled = filename.ends_with(".js") || filename.ends_with(".css");
+    bundled && path.components().any(|component| component.as_os_str() == "dist")
+}
+
+// Only Javascript and CSS are minified or bundled. Data and prose files with
+// long lines are left alone.
+pub fn looks_minified(bytes: &[u8], language: Language) -> bool {
+    if bytes.is_empty() || (language != Language::Javascript && language != Language::Css) {
+        return false;
+    }
+
+    let mut lines = 0;
+    let mut longest = 0;
+    for line in bytes.split(|&b| b == b'\n') {
+        lines += 1;
+        longest = longest.max(line.len());
+    }
+
+    bytes.len() / lines > MINIFIED_AVERAGE_LINE || longest > MINIFIED_LONGEST_LINE
+}
+
+pub fn is_minified(filepath: &str) -> bool {
+    if is_minified_name(filepath) {
+        return true;
+    }
+    match map_file(filepath) {
+        Some(mmap) => looks_minified(&mmap, get_language(filepath)),
+        None => false,
+    }
+}
+
+const VENDORED_DIRS: &[&str] = &[
+    "vendor",
+    "vendors",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "3rdparty",
+    "node_modules",
+    "bower_components",
+    "external",
+    "externals",
+    "deps",
+    "Godeps",
+];
+
+// Libraries that are commonly copied into a source tree
+const VENDORED_FILES: &[&str] = &[
+    "**/sqlite3.c",
+    "**/sqlite3.h",
+    "**/sqlite3ext.h",
+    "**/zlib/**",
+    "**/zlib-*/**",
+    "**/jquery*.js",
+    "**/catch.hpp",
+    "**/doctest.h",
+    "**/stb_*.h",
+];
+
+pub struct Vendored {
+    patterns: GlobSet,
+}
+
+impl Vendored {
+    // Builds the set of vendored locations, extended with user supplied globs.
+    // A glob matching a directory also matches everything below it. Paths are
+    // matched relative to the directory being counted.
+    pub fn new(extra: &[&str]) -> Result<Vendored, globset::Error> {
+        let mut builder = GlobSetBuilder::new();
+        for dir in VENDORED_DIRS {
+            builder.add(Glob::new(&format!("**/{}", dir))?);
+            builder.add(Glob::new(&format!("**/{}/**", dir))?);
+        }
+        for file in VENDORED_FILES {
+            builder.add(Glob::new(file)?);
+        }
+        for pattern in extra {
+            // Like gitignore patterns, a leading / anchors the pattern at the
+            // top of the target, otherwise it matches at any depth
+            let pattern = pattern.trim_end_matches('/');
+            let pattern = if let Some(anchored) = pattern.strip_prefix('/') {
+                String::from(anchored)
+            } else if pattern.starts_with("**/") {
+                String::from(pattern)
+            } else {
+                format!("**/{}", pattern)
+            };
+            builder.add(Glob::new(&pattern)?);
+            builder.add(Glob::new(&format!("{}/**", pattern))?);
+        }
+        Ok(Vendored {
+            patterns: builder.build()?,
+        })
+    }
+
+    pub fn is_vendored(&self, path: &str) -> bool {
+        self.patterns.is_match(path)
+    }
+}
+
+// Binary files are recognized from the first block of the file
+const SNIFF_LEN: usize = 8 * 1024;
+
+const BINARY_MAGIC: &[&[u8]] = &[
+    b"\x7fELF",
+    b"\xcf\xfa\xed\xfe",
+    b"\x89PNG",
+    b"GIF8",
+    b"\xff\xd8\xff",
+    b"PK\x03\x04",
+    b"\x1f\x8b",
+    b"\xfd7zXZ",
+    b"%PDF",
+    b"\x00asm",
+];
+
+pub fn looks_binary(bytes: &[u8]) -> bool {
+    let block = &bytes[..bytes.len().min(SNIFF_LEN)];
+
+    if BINARY_MAGIC.iter().any(|magic| block.starts_with(magic)) || block.contains(&0) {
+        return true;
+    }
+
+    let control = block
+        .iter()
+        .filter(|&&b| b < 0x20 && b != b'\n' && b != b'\r' && b != b'\t' && b != 0x0c)
+        .count();
+    control * 10 > block.len()
+}
+
+pub fn is_binary(filepath: &str) -> bool {
+    let mut file = match File::open(filepath) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+
+    let mut block = vec![0; SNIFF_LEN];
+    let mut len = 0;
+    while len < block.len() {
+        match file.read(&mut block[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(_) => return false,
+        }
+    }
+
+    looks_binary(&block[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generated_from_name_or_banner() {
+        assert!(is_generated_name("api/service.pb.go"));
+        assert!(is_generated_name("Cargo.lock"));
+        assert!(!is_generated_name("src/main.go"));
+        assert!(has_generated_header(b"// Code generated by stringer. DO NOT EDIT.\npackage main\n"));
+        assert!(!has_generated_header(b"package main\n"));
+    }
+
+    #[test]
+    fn minified_only_for_javascript_and_css() {
+        let long_line = vec![b'x'; 300];
+        assert!(looks_minified(&long_line, Language::Javascript));
+        assert!(looks_minified(&long_line, Language::Css));
+        assert!(!looks_minified(&long_line, Language::Json));
+        assert!(!looks_minified(&long_line, Language::Markdown));
+        assert!(!looks_minified(b"var a = 1;\nvar b = 2;\n", Language::Javascript));
+        assert!(is_minified_name("static/app.min.js"));
+        assert!(is_minified_name("dist/app.js"));
+        assert!(!is_minified_name("src/app.js"));
+    }
+
+    #[test]
+    fn vendored_directories_files_and_patterns() {
+        let vendored = Vendored::new(&["/gen", "extern/"]).unwrap();
+        assert!(vendored.is_vendored("vendor/lib.go"));
+        assert!(vendored.is_vendored("web/node_modules/pkg/index.js"));
+        assert!(vendored.is_vendored("src/sqlite3.c"));
+        assert!(vendored.is_vendored("gen/api.rs"));
+        assert!(!vendored.is_vendored("src/gen/api.rs"));
+        assert!(vendored.is_vendored("src/extern/lib.c"));
+        assert!(!vendored.is_vendored("src/main.rs"));
+    }
+
+    #[test]
+    fn binary_from_magic_nul_or_control_bytes() {
+        assert!(looks_binary(b"\x89PNG\r\n\x1a\n"));
+        assert!(looks_binary(b"text with a \0 byte"));
+        assert!(looks_binary(b"\x01\x02\x03\x04abc"));
+        assert!(!looks_binary(b"fn main() {\n\tprintln!(\"hi\");\r\n}\n"));
+        assert!(!looks_binary(b""));
+    }
+}
@@ -0,0 +1,273 @@
+use {classify, trim_bytes, Count, Language, LineKind, Lines};
+
+// Line counts of a comparison between two versions of a file
+#[derive(PartialEq, Default, Clone)]
+pub struct DiffCount {
+    pub same: Count,
+    pub modified: Count,
+    pub added: Count,
+    pub removed: Count,
+}
+
+impl DiffCount {
+    pub fn merge(&mut self, count2: &DiffCount) {
+        self.same.merge(&count2.same);
+        self.modified.merge(&count2.modified);
+        self.added.merge(&count2.added);
+        self.removed.merge(&count2.removed);
+    }
+
+    pub fn is_unchanged(&self) -> bool {
+        self.modified.total == 0 && self.added.total == 0 && self.removed.total == 0
+    }
+}
+
+// Edit distances above this are not searched for, the differing middle of the
+// files is then treated as a single change
+const MAX_EDIT_DISTANCE: usize = 4096;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Edit {
+    Same,
+    Removed,
+    Added,
+}
+
+// Within a changed block, removed and added lines of the same kind are paired
+// up as modified lines
+fn add_hunk(diff: &mut DiffCount, removed: &Count, added: &Count) {
+    let modified_code = removed.code.min(added.code);
+    let modified_comments = removed.comments.min(added.comments);
+    let modified_blank = removed.blank.min(added.blank);
+
+    let modified = Count {
+        code: modified_code,
+        comments: modified_comments,
+        blank: modified_blank,
+        total: modified_code + modified_comments + modified_blank,
+    };
+    diff.modified.merge(&modified);
+
+    diff.removed.code += removed.code - modified_code;
+    diff.removed.comments += removed.comments - modified_comments;
+    diff.removed.blank += removed.blank - modified_blank;
+    diff.removed.total += removed.total - modified.total;
+
+    diff.added.code += added.code - modified_code;
+    diff.added.comments += added.comments - modified_comments;
+    diff.added.blank += added.blank - modified_blank;
+    diff.added.total += added.total - modified.total;
+}
+
+pub fn diff_lines(old: Lines, new: Lines) -> DiffCount {
+    let old: Vec<(&[u8], LineKind)> = old.map(|line| (trim_bytes(line.text), line.kind)).collect();
+    let new: Vec<(&[u8], LineKind)> = new.map(|line| (trim_bytes(line.text), line.kind)).collect();
+
+    let old_text: Vec<&[u8]> = old.iter().map(|&(text, _)| text).collect();
+    let new_text: Vec<&[u8]> = new.iter().map(|&(text, _)| text).collect();
+
+    let mut diff = DiffCount::default();
+    let mut removed = Count::default();
+    let mut added = Count::default();
+    let (mut i, mut j) = (0, 0);
+
+    for edit in edit_script(&old_text, &new_text) {
+        match edit {
+            Edit::Same => {
+                add_hunk(&mut diff, &removed, &added);
+                removed = Count::default();
+                added = Count::default();
+                diff.same.add_line(new[j].1);
+                i += 1;
+                j += 1;
+            }
+            Edit::Removed => {
+                removed.add_line(old[i].1);
+                i += 1;
+            }
+            Edit::Added => {
+                added.add_line(new[j].1);
+                j += 1;
+            }
+        }
+    }
+    add_hunk(&mut diff, &removed, &added);
+
+    diff
+}
+
+pub fn diff_bytes(old: &[u8], new: &[u8], language: Language) -> DiffCount {
+    diff_lines(classify(old, language), classify(new, language))
+}
+
+// Myers' O(ND) difference algorithm in linear space: the middle snake of an
+// optimal path splits the files in two halves that are compared in turn
+fn edit_script(a: &[&[u8]], b: &[&[u8]]) -> Vec<Edit> {
+    let mut edits = Vec::with_capacity(a.len() + b.len());
+    let limit = (MAX_EDIT_DISTANCE as isize + 1) / 2;
+    if !compare(a, b, limit, &mut edits) {
+        edits = vec![Edit::Removed; a.len()];
+        edits.extend(vec![Edit::Added; b.len()]);
+    }
+    edits
+}
+
+// Appends the edits turning a into b, giving up when the middle snake needs
+// more than limit rounds
+fn compare(a: &[&[u8]], b: &[&[u8]], limit: isize, edits: &mut Vec<Edit>) -> bool {
+    let prefix = a.iter().zip(b).take_while(|&(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|&(x, y)| x == y)
+        .count();
+    let a_middle = &a[prefix..a.len() - suffix];
+    let b_middle = &b[prefix..b.len() - suffix];
+
+    if a_middle.is_empty() || b_middle.is_empty() {
+        edits.extend(vec![Edit::Same; prefix]);
+        edits.extend(vec![Edit::Removed; a_middle.len()]);
+        edits.extend(vec![Edit::Added; b_middle.len()]);
+        edits.extend(vec![Edit::Same; suffix]);
+        return true;
+    }
+
+    let (x0, y0, x, y) = match middle_snake(a_middle, b_middle, limit) {
+        Some(snake) => snake,
+        None => return false,
+    };
+
+    // Both halves need fewer edits than the whole, so they are not limited
+    let unlimited = (a.len() + b.len()) as isize;
+    edits.extend(vec![Edit::Same; prefix]);
+    compare(&a_middle[..x0], &b_middle[..y0], unlimited, edits);
+    edits.extend(vec![Edit::Same; x - x0]);
+    compare(&a_middle[x..], &b_middle[y..], unlimited, edits);
+    edits.extend(vec![Edit::Same; suffix]);
+    true
+}
+
+// Searches forwards from the start and backwards from the end at the same
+// time until the paths overlap, and returns the start and end of the snake
+// where they do. The backward search runs on the reversed files.
+fn middle_snake(a: &[&[u8]], b: &[&[u8]], limit: isize) -> Option<(usize, usize, usize, usize)> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let max = ((n + m + 1) / 2).min(limit);
+    let offset = max + 1;
+
+    let mut forward = vec![0isize; 2 * max as usize + 3];
+    let mut backward = vec![0isize; 2 * max as usize + 3];
+
+    for d in 0..max + 1 {
+        let mut k = -d;
+        while k <= d {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && forward[i - 1] < forward[i + 1]) {
+                forward[i + 1]
+            } else {
+                forward[i - 1] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[i] = x;
+            let reverse_k = delta - k;
+            if odd && reverse_k.abs() < d && x + backward[(reverse_k + offset) as usize] >= n {
+                return Some((x0 as usize, y0 as usize, x as usize, y as usize));
+            }
+            k += 2;
+        }
+
+        let mut k = -d;
+        while k <= d {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && backward[i - 1] < backward[i + 1]) {
+                backward[i + 1]
+            } else {
+                backward[i - 1] + 1
+            };
+            let mut y = x - k;
+            let (x0, y0) = (x, y);
+            while x < n && y < m && a[(n - 1 - x) as usize] == b[(m - 1 - y) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[i] = x;
+            let forward_k = delta - k;
+            if !odd && forward_k.abs() <= d && x + forward[(forward_k + offset) as usize] >= n {
+                return Some(((n - x) as usize, (m - y) as usize, (n - x0) as usize, (m - y0) as usize));
+            }
+            k += 2;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Length of the longest common subsequence, by dynamic programming
+    fn lcs(a: &[&[u8]], b: &[&[u8]]) -> usize {
+        let mut table = vec![vec![0; b.len() + 1]; a.len() + 1];
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                table[i + 1][j + 1] = if a[i] == b[j] {
+                    table[i][j] + 1
+                } else {
+                    table[i][j + 1].max(table[i + 1][j])
+                };
+            }
+        }
+        table[a.len()][b.len()]
+    }
+
+    #[test]
+    fn edit_script_is_a_shortest_edit() {
+        let words: [&[u8]; 3] = [b"a", b"b", b"c"];
+        let mut seed = 12345u32;
+        let mut next = || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as usize
+        };
+
+        for _ in 0..500 {
+            let a: Vec<&[u8]> = (0..next() % 12).map(|_| words[next() % 3]).collect();
+            let b: Vec<&[u8]> = (0..next() % 12).map(|_| words[next() % 3]).collect();
+            let edits = edit_script(&a, &b);
+
+            // Replaying the edits on a gives b
+            let (mut i, mut j) = (0, 0);
+            for edit in &edits {
+                match *edit {
+                    Edit::Same => {
+                        assert_eq!(a[i], b[j]);
+                        i += 1;
+                        j += 1;
+                    }
+                    Edit::Removed => i += 1,
+                    Edit::Added => j += 1,
+                }
+            }
+            assert_eq!((i, j), (a.len(), b.len()));
+
+            let same = edits.iter().filter(|&&edit| edit == Edit::Same).count();
+            assert_eq!(same, lcs(&a, &b));
+        }
+    }
+
+    #[test]
+    fn modified_lines_are_paired_by_kind() {
+        let diff = diff_bytes(b"a();\n// old\nb();\n", b"a();\n// new\nb();\nc();\n", Language::C);
+        assert_eq!((diff.same.code, diff.modified.comments, diff.added.code), (2, 1, 1));
+        assert_eq!((diff.removed.total, diff.modified.total), (0, 1));
+    }
+}
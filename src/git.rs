@@ -0,0 +1,321 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use globset::{GlobBuilder, GlobMatcher};
+
+use Language;
+
+fn git_error(message: String) -> io::Error {
+    io::Error::other(message)
+}
+
+// Runs git in dir and returns its output, failing if git exits with an error
+pub fn git<P: AsRef<Path>>(dir: P, args: &[&str]) -> io::Result<Vec<u8>> {
+    let output = Command::new("git").arg("-C").arg(dir.as_ref()).args(args).output()?;
+    if !output.status.success() {
+        return Err(git_error(format!(
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+pub fn git_string<P: AsRef<Path>>(dir: P, args: &[&str]) -> io::Result<String> {
+    let stdout = git(dir, args)?;
+    Ok(String::from_utf8_lossy(&stdout).trim_end_matches('\n').to_string())
+}
+
+#[derive(Clone)]
+pub struct TreeEntry {
+    // Path relative to the top of the repository
+    pub path: String,
+    pub oid: String,
+}
+
+// Lists the files of the tree of rev. Symlinks and submodules are left out.
+pub fn ls_tree<P: AsRef<Path>>(dir: P, rev: &str) -> io::Result<Vec<TreeEntry>> {
+    let stdout = git(dir, &["ls-tree", "-r", "-z", "--full-tree", rev])?;
+    let mut entries = vec![];
+    for record in stdout.split(|&b| b == 0).filter(|record| !record.is_empty()) {
+        let record = String::from_utf8_lossy(record);
+        let (info, path) = match record.find('\t') {
+            Some(n) => (&record[..n], &record[n + 1..]),
+            None => continue,
+        };
+        let fields: Vec<&str> = info.split(' ').collect();
+        if fields.len() != 3 || fields[1] != "blob" || fields[0] == "120000" {
+            continue;
+        }
+        entries.push(TreeEntry {
+            path: String::from(path),
+            oid: String::from(fields[2]),
+        });
+    }
+    Ok(entries)
+}
+
+// Lists the files in the index under paths, relative to dir
+pub fn ls_files<P: AsRef<Path>>(dir: P, paths: &[&str]) -> io::Result<Vec<String>> {
+    let mut args = vec!["ls-files", "-z", "--"];
+    args.extend_from_slice(paths);
+    let stdout = git(dir, &args)?;
+    Ok(stdout
+        .split(|&b| b == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| String::from_utf8_lossy(path).into_owned())
+        .collect())
+}
+
+// Whether dir is a submodule: its parent repository records it in the index
+// as a gitlink, mode 160000. Other checkouts nested in a tree are not.
+pub fn is_submodule<P: AsRef<Path>>(dir: P) -> bool {
+    let dir = dir.as_ref();
+    let name = match dir.file_name().and_then(|name| name.to_str()) {
+        Some(name) => name,
+        None => return false,
+    };
+    let parent = match dir.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    match git(parent, &["ls-files", "--stage", "-z", "--", name]) {
+        Ok(stdout) => stdout.split(|&b| b == 0).any(|record| record.starts_with(b"160000 ")),
+        Err(_) => false,
+    }
+}
+
+// Lists the files under paths that differ between rev and the working tree,
+// and the untracked files that are not ignored. Deleted files are left out.
+pub fn changed_files<P: AsRef<Path>>(dir: P, rev: &str, paths: &[&str]) -> io::Result<Vec<String>> {
+    let mut diff_args = vec!["diff", "--name-only", "-z", "--relative", "--no-renames", "--diff-filter=d", rev, "--"];
+    diff_args.extend_from_slice(paths);
+    let mut untracked_args = vec!["ls-files", "-z", "--others", "--exclude-standard", "--"];
+    untracked_args.extend_from_slice(paths);
+
+    let mut files = vec![];
+    for stdout in &[git(dir.as_ref(), &diff_args)?, git(dir.as_ref(), &untracked_args)?] {
+        files.extend(stdout
+            .split(|&b| b == 0)
+            .filter(|path| !path.is_empty())
+            .map(|path| String::from_utf8_lossy(path).into_owned()));
+    }
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+#[derive(Clone, Default)]
+pub struct BlameLine {
+    pub author: String,
+    pub email: String,
+    // Author time in seconds since the epoch
+    pub time: i64,
+}
+
+// Blames the working tree version of path, giving the commit author of each
+// of its lines in order. Uncommitted lines are attributed to "Not Committed Yet".
+pub fn blame<P: AsRef<Path>>(dir: P, path: &str) -> io::Result<Vec<BlameLine>> {
+    let stdout = git(dir, &["blame", "--line-porcelain", "--", path])?;
+    let mut lines = vec![];
+    let mut line = BlameLine::default();
+    for header in stdout.split(|&b| b == b'\n') {
+        // The contents of the line end its headers
+        if header.first() == Some(&b'\t') {
+            lines.push(line.clone());
+            continue;
+        }
+        let header = String::from_utf8_lossy(header);
+        if let Some(author) = header.strip_prefix("author ") {
+            line.author = String::from(author);
+        } else if let Some(email) = header.strip_prefix("author-mail ") {
+            line.email = String::from(email.trim_matches(|c| c == '<' || c == '>'));
+        } else if let Some(time) = header.strip_prefix("author-time ") {
+            line.time = time.parse().unwrap_or(0);
+        }
+    }
+    Ok(lines)
+}
+
+// Counts the commits since a date that changed each file under paths. Paths
+// are relative to dir.
+pub fn changes<P: AsRef<Path>>(dir: P, since: &str, paths: &[&str]) -> io::Result<HashMap<String, u32>> {
+    let since = format!("--since={}", since);
+    let mut args = vec!["log", "--no-renames", "--relative", "--name-only", "-z", "--format=", &since, "--"];
+    args.extend_from_slice(paths);
+    let stdout = git(dir, &args)?;
+
+    let mut changes = HashMap::new();
+    for path in stdout.split(|&b| b == 0 || b == b'\n').filter(|path| !path.is_empty()) {
+        *changes.entry(String::from_utf8_lossy(path).into_owned()).or_insert(0) += 1;
+    }
+    Ok(changes)
+}
+
+pub struct Commit {
+    pub oid: String,
+    // Committer time in seconds since the epoch
+    pub time: i64,
+    // Committer date as YYYY-MM-DD
+    pub date: String,
+}
+
+// Lists the first parent history of HEAD, oldest first
+pub fn log<P: AsRef<Path>>(dir: P, since: Option<&str>) -> io::Result<Vec<Commit>> {
+    let since = since.map(|since| format!("--since={}", since));
+    let mut args = vec!["log", "--first-parent", "--reverse", "--date=short", "--format=%H %ct %cd"];
+    if let Some(ref since) = since {
+        args.push(since);
+    }
+    args.push("HEAD");
+
+    let mut commits = vec![];
+    for line in git_string(dir, &args)?.lines() {
+        let fields: Vec<&str> = line.split(' ').collect();
+        if fields.len() != 3 {
+            continue;
+        }
+        commits.push(Commit {
+            oid: String::from(fields[0]),
+            time: fields[1].parse().unwrap_or(0),
+            date: String::from(fields[2]),
+        });
+    }
+    Ok(commits)
+}
+
+// Reads objects through a single long running git cat-file --batch
+pub struct BlobReader {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl BlobReader {
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<BlobReader> {
+        let mut child = Command::new("git")
+            .arg("-C")
+            .arg(dir.as_ref())
+            .args(["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().expect("piped stdin");
+        let stdout = BufReader::new(child.stdout.take().expect("piped stdout"));
+        Ok(BlobReader {
+            child,
+            stdin,
+            stdout,
+        })
+    }
+
+    pub fn read(&mut self, oid: &str) -> io::Result<Vec<u8>> {
+        writeln!(self.stdin, "{}", oid)?;
+        self.stdin.flush()?;
+
+        let mut header = String::new();
+        self.stdout.read_line(&mut header)?;
+        let size = match header.split_whitespace().nth(2).map(|size| size.parse::<usize>()) {
+            Some(Ok(size)) => size,
+            _ => return Err(git_error(format!("cannot read object {}: {}", oid, header.trim()))),
+        };
+
+        // The contents are followed by a newline
+        let mut bytes = vec![0; size + 1];
+        self.stdout.read_exact(&mut bytes)?;
+        bytes.truncate(size);
+        Ok(bytes)
+    }
+}
+
+impl Drop for BlobReader {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// Attributes from .gitattributes that change how a file is counted
+#[derive(Clone, Default)]
+pub struct Attributes {
+    pub generated: Option<bool>,
+    pub vendored: Option<bool>,
+    pub language: Option<Language>,
+    pub binary: bool,
+}
+
+struct AttributeRule {
+    matcher: GlobMatcher,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+pub struct GitAttributes {
+    rules: Vec<AttributeRule>,
+}
+
+impl GitAttributes {
+    // Parses .gitattributes files given as (path of the file, contents).
+    // Rules in deeper directories take precedence over shallower ones.
+    pub fn parse(mut files: Vec<(String, Vec<u8>)>) -> GitAttributes {
+        files.sort_by_key(|(path, _)| path.matches('/').count());
+
+        let mut rules = vec![];
+        for (path, contents) in files {
+            let dir = &path[..path.rfind('/').map_or(0, |n| n + 1)];
+            for line in String::from_utf8_lossy(&contents).lines() {
+                let mut fields = line.split_whitespace();
+                let pattern = match fields.next() {
+                    Some(pattern) if !pattern.starts_with('#') => pattern,
+                    _ => continue,
+                };
+
+                let glob = if pattern.trim_start_matches('/').contains('/') {
+                    format!("{}{}", dir, pattern.trim_start_matches('/'))
+                } else {
+                    format!("{}**/{}", dir, pattern)
+                };
+                let matcher = match GlobBuilder::new(&glob).literal_separator(true).build() {
+                    Ok(glob) => glob.compile_matcher(),
+                    Err(_) => continue,
+                };
+
+                let attributes = fields
+                    .map(|field| match field.find('=') {
+                        Some(n) => (String::from(&field[..n]), Some(String::from(&field[n + 1..]))),
+                        None => (String::from(field), None),
+                    })
+                    .collect();
+                rules.push(AttributeRule { matcher, attributes });
+            }
+        }
+        GitAttributes { rules }
+    }
+
+    pub fn attributes(&self, path: &str) -> Attributes {
+        let mut attributes = Attributes::default();
+        for rule in self.rules.iter().filter(|rule| rule.matcher.is_match(path)) {
+            for (name, value) in &rule.attributes {
+                // Set, unset (-attr) or unspecified (!attr)
+                let state = match name.chars().next() {
+                    Some('-') => Some(false),
+                    Some('!') => None,
+                    _ => Some(value.as_ref().map(String::as_str) != Some("false")),
+                };
+                match name.trim_start_matches(['-', '!']) {
+                    "linguist-generated" => attributes.generated = state,
+                    "linguist-vendored" => attributes.vendored = state,
+                    "linguist-language" => {
+                        attributes.language = value.as_ref().and_then(|value| value.parse().ok())
+                    }
+                    "binary" => attributes.binary = state == Some(true),
+                    "text" => attributes.binary = state == Some(false),
+                    _ => (),
+                }
+            }
+        }
+        attributes
+    }
+}
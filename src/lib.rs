@@ -1,14 +1,48 @@
 extern crate fastchr;
+extern crate flate2;
+extern crate globset;
 extern crate memmap;
+extern crate serde_json;
+extern crate tar;
+extern crate zip;
 
 use std::path::Path;
 use std::fs::File;
-use std::cmp::{max, min};
 use std::fmt;
+use std::io::{self, Read};
+use std::str::FromStr;
 
 use fastchr::fastchr;
 use memmap::{Mmap};
 
+mod archive;
+mod classify;
+mod code;
+mod complexity;
+mod definitions;
+mod detect;
+mod diff;
+pub mod git;
+mod literate;
+mod logical;
+mod prose;
+mod tokens;
+
+pub use archive::{is_archive, read_archive};
+pub use classify::{classify, classify_template, tally, Line, LineKind, Lines};
+pub use code::{code_lines, CodeLine, CodeLines};
+pub use complexity::count_complexity_bytes;
+pub use definitions::{count_definitions_bytes, Definitions};
+pub use diff::{diff_bytes, diff_lines, DiffCount};
+pub use detect::{has_generated_header, is_generated, is_generated_name};
+pub use detect::{is_binary, looks_binary};
+pub use detect::{is_minified, is_minified_name, looks_minified, Vendored};
+pub use literate::{file_parts, Part};
+pub use logical::count_logical_bytes;
+pub use prose::{count_prose, count_prose_bytes, Prose};
+
+use literate::language_parts;
+
 #[derive(PartialEq, Default, Clone)]
 pub struct Count {
     pub code: u32,
@@ -24,6 +58,15 @@ impl Count {
         self.comments += count2.comments;
         self.total += count2.total;
     }
+
+    pub fn add_line(&mut self, kind: LineKind) {
+        self.total += 1;
+        match kind {
+            LineKind::Blank => self.blank += 1,
+            LineKind::Comment | LineKind::Doc => self.comments += 1,
+            LineKind::Code | LineKind::Mixed => self.code += 1,
+        }
+    }
 }
 
 pub struct CountTotal {
@@ -40,6 +83,10 @@ pub enum LineConfig<'a> {
         singles: Vec<&'a str>,
         multis: Vec<(&'a str, &'a str)>,
     },
+    Markup {
+        multi: (&'a str, &'a str),
+        verbatim: (&'a str, &'a str),
+    },
 }
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
@@ -58,6 +105,25 @@ pub enum Language {
     Assembly,
     Text,
     Markdown,
+    ReStructuredText,
+    AsciiDoc,
+    Latex,
+    Json,
+    Json5,
+    Yaml,
+    Toml,
+    Ini,
+    Xml,
+    Jinja,
+    Erb,
+    Handlebars,
+    GoTemplate,
+    Twig,
+    Haskell,
+    LiterateHaskell,
+    Org,
+    Notebook,
+    Css,
 }
 use self::Language::*;
 
@@ -78,9 +144,69 @@ impl language {
             Assembly => "Assembly",
             Text => "Plain text",
             Markdown => "Markdown",
+            ReStructuredText => "reStructuredText",
+            AsciiDoc => "AsciiDoc",
+            Latex => "LaTeX",
+            Json => "JSON",
+            Json5 => "JSON5",
+            Yaml => "YAML",
+            Toml => "TOML",
+            Ini => "INI",
+            Xml => "XML",
+            Jinja => "Jinja2",
+            Erb => "ERB",
+            Handlebars => "Handlebars",
+            GoTemplate => "Go template",
+            Twig => "Twig",
+            Haskell => "Haskell",
+            LiterateHaskell => "Literate Haskell",
+            Org => "Org",
+            Notebook => "Jupyter notebook",
+            Css => "CSS",
 
         }
     }
+
+    pub fn is_prose(&self) -> bool {
+        matches!(*self, Text | Markdown | ReStructuredText | AsciiDoc | Latex | Org)
+    }
+
+    pub fn is_data(&self) -> bool {
+        matches!(*self, Json | Json5 | Yaml | Toml | Ini | Xml)
+    }
+
+    pub fn is_template(&self) -> bool {
+        matches!(*self, Jinja | Erb | Handlebars | GoTemplate | Twig)
+    }
+
+    pub fn is_literate(&self) -> bool {
+        matches!(*self, LiterateHaskell | Org | Notebook)
+    }
+}
+
+// Parses language names as used by notebook kernels and code block headers,
+// falling back to file extensions.
+impl FromStr for Language {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Language, Self::Err> {
+        let name = s.trim().to_lowercase();
+        let language = match &*name {
+            "c++" => Cpp,
+            "python" | "python2" | "python3" => Python,
+            "rust" => Rust,
+            "javascript" | "node" => Javascript,
+            "golang" => Go,
+            "assembly" => Assembly,
+            "text" | "plain text" => Text,
+            "haskell" => Haskell,
+            "latex" => Latex,
+            name => language_from_extension(name),
+        };
+        match language {
+            Unknown => Err(()),
+            language => Ok(language),
+        }
+    }
 }
 
 
@@ -108,7 +234,11 @@ pub fn get_language(filepath: &str) -> Language {
         }
     };
 
-    match &*ext {
+    language_from_extension(&ext)
+}
+
+fn language_from_extension(ext: &str) -> Language {
+    match ext {
         "c" => C,
         "cpp" | "cc" => Cpp,
         "h" | "hh" | "hpp" => Header,
@@ -121,19 +251,66 @@ pub fn get_language(filepath: &str) -> Language {
         "makefile" => Makefile,
         "s" | "asm" => Assembly,
         "txt" => Text,
-        "md" => Markdown,
+        "md" | "markdown" => Markdown,
+        "rst" => ReStructuredText,
+        "adoc" | "asciidoc" => AsciiDoc,
+        "tex" => Latex,
+        "json" => Json,
+        "json5" | "jsonc" => Json5,
+        "yaml" | "yml" => Yaml,
+        "toml" => Toml,
+        "ini" | "cfg" => Ini,
+        "xml" | "xsd" | "xsl" | "xslt" => Xml,
+        "j2" | "jinja" | "jinja2" => Jinja,
+        "erb" => Erb,
+        "hbs" | "handlebars" => Handlebars,
+        "tmpl" | "gotmpl" => GoTemplate,
+        "twig" => Twig,
+        "hs" => Haskell,
+        "lhs" => LiterateHaskell,
+        "org" => Org,
+        "ipynb" => Notebook,
+        "css" => Css,
+        _ => Unknown,
+    }
+}
+
+// The host language of a template is given by the extension in front of the
+// template extension, e.g. deploy.yaml.j2 is a Jinja2 template of YAML.
+pub fn get_template_host(filepath: &str) -> Option<Language> {
+    let language = get_language(filepath);
+    if !language.is_template() {
+        return None;
+    }
+
+    let host = match Path::new(filepath).file_stem().and_then(|stem| stem.to_str()) {
+        Some(stem) if Path::new(stem).extension().is_some() => get_language(stem),
         _ => Unknown,
+    };
+
+    match host {
+        Unknown => default_template_host(language),
+        host => Some(host),
+    }
+}
+
+// The host of a template whose file name doesn't give one
+pub(crate) fn default_template_host(template: Language) -> Option<Language> {
+    match template {
+        Handlebars | Twig | Erb => Some(Html),
+        _ => None,
     }
 }
 
 enum CommentStyle<'a> {
     Simple(Option<&'a str>, Option<(&'a str,&'a str)>),
     Extensive(Vec<&'a str>, Vec<(&'a str,&'a str)>),
+    Markup((&'a str,&'a str), (&'a str,&'a str)),
 }
 
 use self::CommentStyle::*;
 
-fn get_language_config<'a>(language: &Language) ->LineConfig <'a> {
+pub(crate) fn get_language_config<'a>(language: &Language) ->LineConfig <'a> {
     let c_style = Simple(Some("//"), Some(("/*", "*/")));
     let html_style = Simple(None, Some(("<!--", "-->")));
     let no_comments = Simple(None, None);
@@ -142,8 +319,21 @@ fn get_language_config<'a>(language: &Language) ->LineConfig <'a> {
     let comment_style = match *language {
         C | Cpp | Rust | Java | Go | Javascript | Header => c_style,
         Assembly => Simple(Some("#"), Some(("/*", "*/"))),
+        Css => Simple(None, Some(("/*", "*/"))),
         Python => Simple(Some("#"), Some(("'''", "'''"))),
-        Text | Markdown | Unknown => no_comments,
+        Text | Markdown | ReStructuredText | Json | Notebook | Unknown => no_comments,
+        Haskell | LiterateHaskell => Simple(Some("--"), Some(("{-", "-}"))),
+        Org => Simple(Some("# "), None),
+        Json5 => Simple(Some("//"), Some(("/*", "*/"))),
+        Yaml | Toml => Simple(Some("#"), None),
+        Ini => Extensive(vec![";", "#"], vec![]),
+        Xml => Markup(("<!--", "-->"), ("<![CDATA[", "]]>")),
+        Jinja | Twig => Extensive(vec![], vec![("{#", "#}")]),
+        Erb => Extensive(vec![], vec![("<%#", "%>")]),
+        Handlebars => Extensive(vec![], vec![("{{!--", "--}}"), ("{{!", "}}")]),
+        GoTemplate => Extensive(vec![], vec![("{{/*", "*/}}"), ("{{- /*", "*/ -}}")]),
+        AsciiDoc => Simple(Some("//"), Some(("////", "////"))),
+        Latex => Simple(Some("%"), None),
         Makefile => sh_style,
         Html => html_style,
     };
@@ -157,18 +347,146 @@ fn get_language_config<'a>(language: &Language) ->LineConfig <'a> {
             singles,
             multis
         },
+        Markup(multi,verbatim) => LineConfig::Markup {
+            multi,
+            verbatim
+        },
+    }
+}
+
+// Comment openings that mark documentation rather than plain comments
+pub(crate) fn get_doc_markers(language: &Language) -> &'static [&'static str] {
+    match *language {
+        Rust => &["///", "//!", "/**", "/*!"],
+        C | Cpp | Header | Java | Javascript | Css | Json5 => &["/**", "///"],
+        Python => &["'''"],
+        Haskell | LiterateHaskell => &["-- |", "-- ^", "{-|"],
+        _ => &[],
+    }
+}
+
+// How string and char literals are written in a language
+#[derive(Clone, Copy, Default)]
+pub(crate) struct StringSyntax {
+    // Quotes of string literals, longest first
+    pub(crate) quotes: &'static [&'static str],
+    // Quotes of the strings that can go on past the end of their line
+    pub(crate) multiline: &'static [&'static str],
+    // Whether ' starts char literals
+    pub(crate) chars: bool,
+}
+
+pub(crate) fn get_string_syntax(language: &Language) -> StringSyntax {
+    let (quotes, multiline, chars): (&'static [&'static str], &'static [&'static str], bool) = match *language {
+        C | Cpp | Header | Java | Haskell | LiterateHaskell => (&["\""], &[], true),
+        Rust => (&["\""], &["\""], true),
+        Go => (&["\"", "`"], &["`"], true),
+        Javascript => (&["\"", "'", "`"], &["`"], false),
+        Python => (&["\"\"\"", "\"", "'"], &["\"\"\""], false),
+        Css => (&["\"", "'"], &[], false),
+        _ => (&[], &[], false),
+    };
+    StringSyntax { quotes, multiline, chars }
+}
+
+// Keywords and operators that branch, used to estimate complexity. Longer
+// operators come before their prefixes.
+pub(crate) fn get_decision_points(language: &Language) -> &'static [&'static str] {
+    match *language {
+        C | Cpp | Header | Java => &["if", "for", "while", "case", "catch", "&&", "||", "?"],
+        // ?? short circuits like ||, so only the ternary is left for ?
+        Javascript => &["if", "for", "while", "case", "catch", "&&", "||", "??", "?"],
+        Go => &["if", "for", "case", "&&", "||"],
+        Rust => &["if", "for", "while", "=>", "&&", "||"],
+        Python => &["if", "elif", "for", "while", "except", "case", "and", "or"],
+        Haskell | LiterateHaskell => &["if", "case", "&&", "||"],
+        _ => &[],
+    }
+}
+
+// Operators that start like a decision point but don't branch. They are
+// matched before the decision points so that they are not counted.
+pub(crate) fn get_plain_operators(language: &Language) -> &'static [&'static str] {
+    match *language {
+        // Optional chaining, a?.b
+        Javascript => &["?."],
+        _ => &[],
+    }
+}
+
+// How function and type definitions are recognised
+pub(crate) enum DefinitionStyle {
+    // Definitions start with a keyword and have a body in braces, or end with a
+    // semicolon for types without fields
+    Keyword {
+        functions: &'static [&'static str],
+        types: &'static [&'static str],
+        // Blocks of methods and trait implementations
+        impls: &'static [&'static str],
+    },
+    // Functions are a parameter list followed by a body in braces, as in C
+    Signature { types: &'static [&'static str] },
+    // Definitions start with a keyword and have an indented body
+    Indented { functions: &'static [&'static str], types: &'static [&'static str] },
+}
+
+pub(crate) fn get_definition_style(language: &Language) -> Option<DefinitionStyle> {
+    match *language {
+        Rust => Some(DefinitionStyle::Keyword {
+            functions: &["fn"],
+            types: &["struct", "enum", "trait", "union"],
+            impls: &["impl"],
+        }),
+        Go => Some(DefinitionStyle::Keyword {
+            functions: &["func"],
+            types: &["struct", "interface"],
+            impls: &[],
+        }),
+        Javascript => Some(DefinitionStyle::Keyword {
+            functions: &["function"],
+            types: &["class"],
+            impls: &[],
+        }),
+        C => Some(DefinitionStyle::Signature { types: &["struct", "union", "enum"] }),
+        Cpp | Header => Some(DefinitionStyle::Signature { types: &["class", "struct", "union", "enum"] }),
+        Java => Some(DefinitionStyle::Signature { types: &["class", "interface", "enum"] }),
+        Python => Some(DefinitionStyle::Indented {
+            functions: &["def", "async def"],
+            types: &["class"],
+        }),
+        _ => None,
+    }
+}
+
+// What ends a statement, for logical line counts
+#[derive(PartialEq, Clone, Copy)]
+pub(crate) enum StatementStyle {
+    // A semicolon outside parentheses, as in C
+    Terminated,
+    // A newline outside brackets, or a semicolon, as in Python
+    Newline,
+    // A semicolon, or a block expression such as if or match, as in Rust
+    Expression,
+}
+
+pub(crate) fn get_statement_style(language: &Language) -> Option<StatementStyle> {
+    match *language {
+        C | Cpp | Header | Java | Javascript => Some(StatementStyle::Terminated),
+        Python => Some(StatementStyle::Newline),
+        Rust => Some(StatementStyle::Expression),
+        _ => None,
     }
 }
 
-struct ByteLinesState<'a> {
+pub(crate) struct ByteLinesState<'a> {
     buf: &'a [u8],
     pos: usize,
 }
 
-struct ByteLines<'a>(&'a [u8]);
+pub(crate) struct ByteLines<'a>(pub(crate) &'a [u8]);
 
 impl <'a> ByteLines <'a> {
-    fn lines(&self)-> ByteLinesState {
+    pub(crate) fn lines(&self)-> ByteLinesState<'a> {
         ByteLinesState {
             buf: self.0,
             pos: 0,
@@ -197,149 +515,150 @@ impl<'a> Iterator for ByteLinesState<'a> {
     }
 }
 
-
-pub fn count(filepath :&str) -> Count {
-    let language = get_language(filepath);
-    let cfg = get_language_config(&language);
-    match cfg {
-        LineConfig::Normal {single, multi} => count_normal(filepath,single,multi),
-        LineConfig::Everything { singles, multis } => {
-            count_everything(filepath,&singles,&multis)
-        }
-    }
+// The bytes without leading and trailing ASCII whitespace
+pub(crate) fn trim_bytes(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(start, |n| n + 1);
+    &bytes[start..end]
 }
 
 
-pub fn count_normal(filepath: &str, single: Option<&str>, multi: Option<(&str,&str)>) -> Count {
-
-    let file = File::open(filepath);
-    let file = match file {
-        Ok(file) => file,
-        Err(_) => {
-            return Count::default();
-        },
+// Templates recognize their own comments on top of the comments of the host language
+pub(crate) fn get_template_config<'a>(template: &Language, host: Option<Language>) -> LineConfig<'a> {
+    let (mut singles, mut multis) = match get_language_config(template) {
+        LineConfig::Everything { singles, multis } => (singles, multis),
+        _ => (vec![], vec![]),
     };
 
-    let fmmap = unsafe {
-        match Mmap::map(&file) {
-            Ok(mmap) => mmap,
-            Err(_) => {
-                return Count::default();
-            },
+    match host.map(|host| get_language_config(&host)) {
+        Some(LineConfig::Normal { single, multi }) => {
+            singles.extend(single);
+            multis.extend(multi);
         }
-    };
+        Some(LineConfig::Everything { singles: host_singles, multis: host_multis }) => {
+            singles.extend(host_singles);
+            multis.extend(host_multis);
+        }
+        Some(LineConfig::Markup { multi, .. }) => multis.push(multi),
+        None => (),
+    }
 
-    let bytes: &[u8] = &fmmap;
+    LineConfig::Everything { singles, multis }
+}
 
-    let mut count = Count::default();
-    let mut in_comment = false;
 
-    for byte_line in ByteLines(bytes).lines() {
-        let line = match std::str::from_utf8(byte_line) {
-            Ok(s) => s,
-            Err(_) => return Count::default(),
-        };
+pub fn count(filepath :&str) -> Count {
+    match map_file(filepath) {
+        Some(mmap) => count_named(filepath, &mmap),
+        None => Count::default(),
+    }
+}
 
-        count.total += 1;
+// Counts a buffer holding the contents of the file at filepath, which is only
+// used to detect the language. All languages of a literate source are added up.
+pub fn count_named(filepath: &str, bytes: &[u8]) -> Count {
+    let mut count = Count::default();
+    for part in file_parts(filepath, get_language(filepath), bytes) {
+        count.merge(&part.count());
+    }
+    count
+}
 
-        let line = line.trim();
-        if line.is_empty() {
-            count.blank += 1;
-            continue;
-        }
+// Counts a buffer in the same way as a file in language, so literate sources
+// are split and templates get the comments of their usual host
+pub fn count_bytes(bytes: &[u8], language: Language) -> Count {
+    let mut count = Count::default();
+    for part in language_parts(bytes, language, default_template_host(language)) {
+        count.merge(&part.count());
+    }
+    count
+}
 
+pub fn count_template_bytes(bytes: &[u8], template: Language, host: Option<Language>) -> Count {
+    tally(bytes, classify_template(bytes, template, host).map(|line| line.kind))
+}
 
-        if let Some(single) = single {
-            if !in_comment && line.starts_with(single) {
-                if let Some((multi_start, _)) = multi {
-                    if !line.starts_with(multi_start) {
-                        count.comments += 1;
-                        continue;
-                    }
-                } else {
-                    count.comments += 1;
-                    continue;
-                }
-            }
-        }
+pub fn count_reader<R: Read>(mut reader: R, language: Language) -> io::Result<Count> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+    Ok(count_bytes(&bytes, language))
+}
 
-        let (multi_start,multi_end) = match multi {
-            Some(multi) => multi,
-            None => {
-                count.code += 1; // Skip checking for comments if multiline commes are None
-                continue;
-            },
-        };
+fn map_file(filepath: &str) -> Option<Mmap> {
+    let file = match File::open(filepath) {
+        Ok(file) => file,
+        Err(_) => return None,
+    };
+    unsafe { Mmap::map(&file).ok() }
+}
 
-        if !(line.contains(multi_start) || line.contains(multi_end)) {
-            if in_comment {
-                count.comments += 1;
-            } else {
-                count.code += 1;
-            }
-            continue;
-        }
 
+pub fn count_normal(filepath: &str, single: Option<&str>, multi: Option<(&str,&str)>) -> Count {
+    match map_file(filepath) {
+        Some(mmap) => count_normal_bytes(&mmap, single, multi),
+        None => Count::default(),
+    }
+}
 
-        let start_len = multi_start.len();
-        let end_len = multi_end.len();
-        let line_len = line.len();
+fn count_normal_bytes(bytes: &[u8], single: Option<&str>, multi: Option<(&str,&str)>) -> Count {
+    let lines = Lines::new(bytes, LineConfig::Normal { single, multi }, &[], StringSyntax::default());
+    tally(bytes, lines.map(|line| line.kind))
+}
 
-        let mut pos = 0;
-        let mut found_code = false;
-        let contains_utf8 = (0..line_len).any(|i| !line.is_char_boundary(i));
 
+pub fn count_everything<'a>(filepath: &str, singles: &[&'a str], multis: &[(&'a str, &'a str)]) -> Count {
+    match map_file(filepath) {
+        Some(mmap) => count_everything_bytes(&mmap, singles, multis),
+        None => Count::default(),
+    }
+}
 
-        'outer: while pos < line_len {
-            if contains_utf8 {
-                for i in pos..pos + min(max(start_len, end_len) + 1, line_len - pos) {
-                    if !line.is_char_boundary(i) {
-                        pos += 1;
-                        continue 'outer;
-                    }
-                }
-            }
+fn count_everything_bytes<'a>(bytes: &[u8], singles: &[&'a str], multis: &[(&'a str, &'a str)]) -> Count {
+    let cfg = LineConfig::Everything {
+        singles: singles.to_vec(),
+        multis: multis.to_vec(),
+    };
+    tally(bytes, Lines::new(bytes, cfg, &[], StringSyntax::default()).map(|line| line.kind))
+}
 
-            if !in_comment && pos + start_len <= line_len
-                && &line[pos..pos + start_len] == multi_start
-            {
-                pos += start_len;
-                in_comment = true;
-            } else if in_comment && pos + end_len <= line_len
-                && &line[pos..pos + end_len] == multi_end
-            {
-                pos += end_len;
-                in_comment = false;
-            } else if !in_comment && !&line[pos..pos + 1].chars().next().unwrap().is_whitespace() {
-                pos += 1;
-                found_code = true;
-            } else {
-                pos += 1;
-            }
-        }
 
-        if found_code {
-            count.code += 1;
-        } else {
-            count.comments += 1;
-        }
+pub fn count_markup(filepath: &str, multi: (&str, &str), verbatim: (&str, &str)) -> Count {
+    match map_file(filepath) {
+        Some(mmap) => count_markup_bytes(&mmap, multi, verbatim),
+        None => Count::default(),
     }
+}
 
-    count
+fn count_markup_bytes(bytes: &[u8], multi: (&str, &str), verbatim: (&str, &str)) -> Count {
+    let lines = Lines::new(bytes, LineConfig::Markup { multi, verbatim }, &[], StringSyntax::default());
+    tally(bytes, lines.map(|line| line.kind))
 }
 
 
-pub fn count_everything<'a>(filepath: &str, singles: &[&'a str], multis: &[(&'a str, &'a str)]) -> Count {
-    let mut total_count = Count::default();
-    for single in singles.iter() {
-        let count = count_normal(filepath,Some(single),None);
-        total_count.comments += count.comments;
-        total_count.code -= count.comments;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(count: &Count) -> (u32, u32, u32, u32) {
+        (count.total, count.blank, count.comments, count.code)
     }
-    for &(multi_start,multi_end) in multis {
-        let count = count_normal(filepath,None,Some((multi_start,multi_end)));
-        total_count.comments += count.comments;
-        total_count.code -= count.comments;
+
+    #[test]
+    fn readers_count_like_named_files() {
+        let notebook: &[u8] = br##"{
+  "metadata": {"kernelspec": {"language": "python"}},
+  "cells": [
+    {"cell_type": "code", "source": ["x = 1\n", "# note\n"]},
+    {"cell_type": "markdown", "source": "Some text\n"}
+  ]
+}"##;
+        let named = count_named("a.ipynb", notebook);
+        assert_eq!(fields(&count_reader(notebook, Language::Notebook).unwrap()), fields(&named));
+        assert_eq!((named.code, named.comments), (2, 1));
+
+        let template: &[u8] = b"<!-- note -->\n<p><%= name %></p>\n";
+        let named = count_named("page.erb", template);
+        assert_eq!(fields(&count_reader(template, Language::Erb).unwrap()), fields(&named));
+        assert_eq!((named.code, named.comments), (1, 1));
     }
-    total_count
 }
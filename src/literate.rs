@@ -0,0 +1,208 @@
+use std::borrow::Cow;
+
+use serde_json::{self, Value};
+
+use prose::count_prose_bytes;
+use {classify, classify_template, get_template_host, tally};
+use {ByteLines, Count, Language, Line, Lines, Prose};
+use Language::*;
+
+// The lines of a file written in one language. Most files are a single part,
+// literate sources have one for each language they contain.
+pub struct Part<'a> {
+    pub language: Language,
+    pub host: Option<Language>,
+    pub bytes: Cow<'a, [u8]>,
+    // The line number in the file of each line of a literate part, 0 where it
+    // is not known
+    numbers: Vec<u32>,
+}
+
+impl<'a> Part<'a> {
+    pub fn lines<'b>(&'b self) -> Lines<'b> {
+        if self.language.is_template() {
+            classify_template(&self.bytes, self.language, self.host)
+        } else {
+            classify(&self.bytes, self.language)
+        }
+    }
+
+    pub fn count(&self) -> Count {
+        tally(&self.bytes, self.lines().map(|line| line.kind))
+    }
+
+    pub fn prose(&self) -> Option<Prose> {
+        if self.language.is_prose() {
+            Some(count_prose_bytes(&self.bytes, self.language))
+        } else {
+            None
+        }
+    }
+
+    // The number of a line of the part in the whole file
+    pub fn line_number(&self, line: &Line) -> Option<u32> {
+        if self.numbers.is_empty() {
+            return Some(line.number);
+        }
+        match self.numbers.get(line.number as usize - 1) {
+            Some(&0) | None => None,
+            Some(&number) => Some(number),
+        }
+    }
+}
+
+// Splits the contents of the file at filepath into the parts to count. This
+// is where files are counted from, so that every command treats literate
+// sources and templates the same way.
+pub fn file_parts<'a>(filepath: &str, language: Language, bytes: &'a [u8]) -> Vec<Part<'a>> {
+    language_parts(bytes, language, get_template_host(filepath))
+}
+
+// Splits a buffer whose language is known without a file name. The host is
+// only used by templates.
+pub(crate) fn language_parts<'a>(bytes: &'a [u8], language: Language, host: Option<Language>) -> Vec<Part<'a>> {
+    if !language.is_literate() {
+        return vec![Part {
+            language,
+            host,
+            bytes: Cow::Borrowed(bytes),
+            numbers: vec![],
+        }];
+    }
+
+    let parts = match language {
+        Notebook => split_notebook(bytes),
+        LiterateHaskell => split_literate_haskell(bytes),
+        Org => split_org(bytes),
+        _ => vec![],
+    };
+
+    parts
+        .into_iter()
+        .filter(|part| part.language != Unknown)
+        .collect()
+}
+
+fn push_line(parts: &mut Vec<Part<'static>>, language: Language, line: &[u8], number: u32) {
+    let position = match parts.iter().position(|part| part.language == language) {
+        Some(position) => position,
+        None => {
+            parts.push(Part {
+                language,
+                host: None,
+                bytes: Cow::Owned(vec![]),
+                numbers: vec![],
+            });
+            parts.len() - 1
+        }
+    };
+    let part = &mut parts[position];
+    let buffer = part.bytes.to_mut();
+    buffer.extend_from_slice(line);
+    buffer.push(b'\n');
+    part.numbers.push(number);
+}
+
+// Code cells are counted in the kernel language and markdown cells as prose.
+// Cell lines are not mapped back to lines of the JSON document.
+fn split_notebook(bytes: &[u8]) -> Vec<Part<'static>> {
+    let notebook: Value = match serde_json::from_slice(bytes) {
+        Ok(notebook) => notebook,
+        Err(_) => return vec![],
+    };
+
+    let kernel = notebook["metadata"]["kernelspec"]["language"]
+        .as_str()
+        .or_else(|| notebook["metadata"]["language_info"]["name"].as_str())
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(Unknown);
+
+    let mut parts = vec![];
+    let cells = match notebook["cells"].as_array() {
+        Some(cells) => cells,
+        None => return parts,
+    };
+
+    for cell in cells {
+        let language = match cell["cell_type"].as_str() {
+            Some("code") => kernel,
+            Some("markdown") => Markdown,
+            _ => continue,
+        };
+
+        let source = match cell["source"] {
+            Value::String(ref source) => source.clone(),
+            Value::Array(ref lines) => lines.iter().filter_map(|line| line.as_str()).collect(),
+            _ => continue,
+        };
+
+        for line in ByteLines(source.as_bytes()).lines() {
+            push_line(&mut parts, language, line, 0);
+        }
+    }
+
+    parts
+}
+
+// Supports both bird tracks ("> code") and \begin{code} ... \end{code} blocks
+fn split_literate_haskell(bytes: &[u8]) -> Vec<Part<'static>> {
+    let text = if bytes.windows(12).any(|w| w == b"\\begin{code}") {
+        Latex
+    } else {
+        Text
+    };
+
+    let mut parts = vec![];
+    let mut in_code = false;
+
+    for (n, line) in ByteLines(bytes).lines().enumerate() {
+        let number = n as u32 + 1;
+        if in_code {
+            if line.starts_with(b"\\end{code}") {
+                in_code = false;
+                push_line(&mut parts, text, line, number);
+            } else {
+                push_line(&mut parts, Haskell, line, number);
+            }
+        } else if line.starts_with(b"\\begin{code}") {
+            in_code = true;
+            push_line(&mut parts, text, line, number);
+        } else if line.starts_with(b">") {
+            push_line(&mut parts, Haskell, &line[1..], number);
+        } else {
+            push_line(&mut parts, text, line, number);
+        }
+    }
+
+    parts
+}
+
+// Source blocks in a known language are counted as that language, everything
+// else, including the block delimiters, is counted as Org prose
+fn split_org(bytes: &[u8]) -> Vec<Part<'static>> {
+    let mut parts = vec![];
+    let mut block: Option<Language> = None;
+
+    for (n, line) in ByteLines(bytes).lines().enumerate() {
+        let number = n as u32 + 1;
+        let header = String::from_utf8_lossy(line).trim().to_lowercase();
+
+        match block {
+            Some(_) if header.starts_with("#+end_src") => {
+                block = None;
+                push_line(&mut parts, Org, line, number);
+            }
+            Some(language) => push_line(&mut parts, language, line, number),
+            None if header.starts_with("#+begin_src") => {
+                block = header
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|name| name.parse().ok());
+                push_line(&mut parts, Org, line, number);
+            }
+            None => push_line(&mut parts, Org, line, number),
+        }
+    }
+
+    parts
+}
@@ -0,0 +1,167 @@
+use std::str;
+
+use code::code_lines;
+use tokens::{find_word, is_word_byte, word_len};
+use {get_statement_style, trim_bytes, Language, LineKind, StatementStyle};
+
+// Block expressions that are statements of their own when they start one
+const BLOCK_EXPRESSIONS: &[&[u8]] = &[b"if", b"match", b"for", b"while", b"loop", b"unsafe"];
+
+// Keywords before a brace that opens a block of statements
+const STATEMENT_BLOCKS: &[&str] = &["fn", "if", "else", "for", "while", "loop", "unsafe", "async"];
+
+// Whether the brace after head opens a block of statements: a function body,
+// a branch or loop body, a closure, a match arm or a bare block. Struct
+// literals, match arms and items such as structs hold no statements.
+fn opens_statements(head: &[u8]) -> bool {
+    let head = trim_bytes(head);
+    head.is_empty()
+        || head.ends_with(b"=>")
+        || head.ends_with(b"=")
+        || head.ends_with(b"|")
+        || STATEMENT_BLOCKS.iter().any(|keyword| find_word(head, keyword).is_some())
+}
+
+// Counts logical lines of code: statements rather than physical lines, so the
+// count doesn't depend on how the code is formatted. Returns None for
+// languages without a statement style.
+//
+// In Rust the tail expression of a block of statements counts as one, as in
+// fn f() -> u32 { x + 1 }. Match arms without braces are part of the match
+// and are not counted.
+pub fn count_logical_bytes(bytes: &[u8], language: Language) -> Option<u32> {
+    let style = get_statement_style(&language)?;
+    if str::from_utf8(bytes).is_err() {
+        return None;
+    }
+
+    let mut statements = 0;
+    // Depth of parentheses and brackets, and of braces too where they don't
+    // hold statements
+    let mut depth = 0i32;
+    // Whether the next code starts a statement
+    let mut at_start = true;
+    // Whether the statement being read was counted when it started, as block
+    // expressions are
+    let mut counted = false;
+    // Code of the statement being read since the last brace
+    let mut head = vec![];
+    // Braces open in Rust: whether each holds statements, and whether the
+    // statement around it was already counted
+    let mut blocks: Vec<(bool, bool)> = vec![];
+
+    let mut lines = code_lines(bytes, language).peekable();
+    while let Some(line) = lines.next() {
+        if line.line.kind != LineKind::Code && line.line.kind != LineKind::Mixed {
+            continue;
+        }
+        let code = &line.code;
+
+        let mut pos = 0;
+        while pos < code.len() {
+            let b = code[pos];
+            match b {
+                b'(' | b'[' => depth += 1,
+                b')' | b']' => depth -= 1,
+                b'{' if style == StatementStyle::Newline => depth += 1,
+                b'}' if style == StatementStyle::Newline => depth -= 1,
+                b';' if depth <= 0 => {
+                    statements += 1;
+                    at_start = true;
+                    counted = false;
+                    head.clear();
+                }
+                b'{' if style == StatementStyle::Expression => {
+                    blocks.push((opens_statements(&head), counted));
+                    at_start = true;
+                    counted = false;
+                    head.clear();
+                }
+                b'}' if style == StatementStyle::Expression => {
+                    match blocks.pop() {
+                        // A struct literal or a match goes on as part of the
+                        // statement around it
+                        Some((false, false)) => at_start = false,
+                        Some((false, true)) => at_start = true,
+                        _ => {
+                            if !at_start && !counted {
+                                statements += 1;
+                            }
+                            at_start = true;
+                        }
+                    }
+                    counted = false;
+                    head.clear();
+                }
+                b'{' | b'}' => at_start = true,
+                _ if style == StatementStyle::Expression && at_start && is_word_byte(b) => {
+                    let len = word_len(&code[pos..]);
+                    if BLOCK_EXPRESSIONS.contains(&&code[pos..pos + len]) {
+                        statements += 1;
+                        counted = true;
+                    }
+                    at_start = false;
+                    head.extend_from_slice(&code[pos..pos + len]);
+                    pos += len;
+                    continue;
+                }
+                _ if !b.is_ascii_whitespace() => at_start = false,
+                _ => (),
+            }
+            if b != b';' && b != b'{' && b != b'}' {
+                head.push(b);
+            }
+            pos += 1;
+        }
+        head.push(b' ');
+
+        // A newline outside brackets, strings and explicit line joins ends a statement
+        if style == StatementStyle::Newline {
+            let joined = trim_bytes(code).last() == Some(&b'\\');
+            let in_string = lines.peek().is_some_and(|next| next.continued);
+            if depth <= 0 && !joined && !in_string && !at_start {
+                statements += 1;
+                at_start = true;
+            }
+        }
+    }
+
+    Some(statements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn logical(source: &str, language: Language) -> u32 {
+        count_logical_bytes(source.as_bytes(), language).unwrap()
+    }
+
+    #[test]
+    fn c_statements_end_with_semicolons_outside_parentheses() {
+        let source = "int sum(int n) {\n    int a = 0;\n    for (int i = 0; i < n; i++) {\n        a += i;\n    }\n    return a;\n}\n";
+        assert_eq!(logical(source, Language::C), 3);
+    }
+
+    #[test]
+    fn python_brackets_and_strings_continue_a_statement() {
+        let source = "x = [\n    1,\n    2,\n]\ny = f(a,\n      b)\ns = \"\"\"doc\ntext\"\"\"\nz = 1; w = 2\nt = 1 + \\\n    2\n";
+        assert_eq!(logical(source, Language::Python), 6);
+    }
+
+    #[test]
+    fn rust_tail_expressions_are_statements() {
+        assert_eq!(logical("fn f() -> u32 { let x = 1; x + 1 }\n", Language::Rust), 2);
+        let source = "fn g(v: Option<u32>) -> u32 {\n    if let Some(n) = v {\n        n\n    } else {\n        0\n    }\n}\n";
+        assert_eq!(logical(source, Language::Rust), 3);
+    }
+
+    #[test]
+    fn rust_struct_literals_and_match_arms_are_not_blocks() {
+        let source = "fn h() -> Point {\n    let p = Point { x: 1 };\n    Point { x: p.x }\n}\n";
+        assert_eq!(logical(source, Language::Rust), 2);
+        let source = "fn m(x: u32) -> u32 {\n    match x {\n        0 => 1,\n        _ => { log(); 2 }\n    }\n}\n";
+        assert_eq!(logical(source, Language::Rust), 3);
+        assert_eq!(logical("struct S { a: u32 }\nimpl S {\n    fn a(&self) -> u32 { self.a }\n}\n", Language::Rust), 1);
+    }
+}
@@ -2,22 +2,35 @@ extern crate cl;
 extern crate clap;
 extern crate deque;
 extern crate ignore;
+extern crate memmap;
 extern crate num_cpus;
+#[macro_use]
+extern crate serde_json;
 
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{self, File};
+use std::io::{self, Read};
 use std::collections::hash_map::Entry;
 use std::thread;
 use std::str::FromStr;
-use std::sync::atomic::{AtomicBool,Ordering};
-use std::sync::mpsc::channel;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool,AtomicUsize,Ordering};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::{Arc, Mutex};
 
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use ignore::{WalkBuilder, WalkState};
 
 use deque::{Stealer, Stolen};
+use memmap::Mmap;
 
 use cl::*;
+use cl::git::{self, Attributes};
+
+mod commands;
+
+use commands::history::Step;
 
 #[derive(PartialEq,Eq)]
 enum Sort {
@@ -44,38 +57,189 @@ impl FromStr for Sort {
     }
 }
 
+#[derive(PartialEq,Eq,Clone,Copy)]
+enum Mode {
+    Include,
+    Exclude,
+    Separate,
+}
+
+impl FromStr for Mode {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Mode,Self::Err> {
+        match s {
+            "include" | "Include" => Ok(Mode::Include),
+            "exclude" | "Exclude" => Ok(Mode::Exclude),
+            "separate" | "Separate" => Ok(Mode::Separate),
+            _ => Err(())
+        }
+    }
+}
+
+#[derive(Clone)]
+struct Filters {
+    generated: Mode,
+    minified: Mode,
+    vendored: Mode,
+    vendored_patterns: Arc<Vendored>,
+    submodules: Mode,
+}
+
+impl Filters {
+    // Files counted apart from the main report, grouped under a heading
+    fn bucket(&self, fc: &FileCount, submodules: &[String]) -> Option<String> {
+        let submodule = submodules
+            .iter()
+            .filter(|root| fc.path.starts_with(&format!("{}/", root)))
+            .max_by_key(|root| root.len());
+        if let Some(root) = submodule {
+            Some(format!("Submodule {}", root))
+        } else if fc.generated && self.generated == Mode::Separate {
+            Some(String::from("Generated files"))
+        } else if fc.minified && self.minified == Mode::Separate {
+            Some(String::from("Minified files"))
+        } else if fc.vendored && self.vendored == Mode::Separate {
+            Some(String::from("Vendored files"))
+        } else {
+            None
+        }
+    }
+}
+
+// Optional measurements made on top of the line counts
+#[derive(Clone, Copy, Default)]
+struct Metrics {
+    complexity: bool,
+    definitions: bool,
+    logical: bool,
+}
+
 enum Work {
-    File(String),
+    File(String, Attributes),
+    Buffer(String, Vec<u8>, Attributes),
     Quit,
 }
 
 struct Worker {
     stealer: Stealer<Work>,
+    queued: Arc<AtomicUsize>,
+    filters: Filters,
+    metrics: Metrics,
 }
 
 impl Worker {
-    fn run(self) -> Vec<FileCount> {
+    fn run(self) -> (Vec<FileCount>, Vec<Diagnostic>) {
         let mut file_counts: Vec<FileCount> = vec![];
+        let mut diagnostics: Vec<Diagnostic> = vec![];
         loop {
             match self.stealer.steal() {
                 Stolen::Empty => continue,
                 Stolen::Data(Work::Quit) => break,
-                Stolen::Data(Work::File(path)) => {
-                    let language = get_language(&path);
-                    if language != language::Unknown {
-                        let count = count(&path);
-                        file_counts.push(FileCount {
-                            language: language,
-                            path: path,
-                            count: count,
-                        });
+                Stolen::Data(Work::File(path, attributes)) => {
+                    self.queued.fetch_sub(1, Ordering::SeqCst);
+                    let language = attributes.language.unwrap_or_else(|| get_language(&path));
+                    if language == Language::Unknown {
+                        continue;
+                    }
+                    let file = File::open(&path);
+                    // Empty files cannot be mapped and are counted as an empty buffer
+                    let mmap = file.ok().and_then(|file| unsafe { Mmap::map(&file).ok() });
+                    let bytes: &[u8] = match mmap {
+                        Some(ref mmap) => mmap,
+                        None => &[],
                     };
+                    match count_buffer(path, language, bytes, &attributes, &self.filters, self.metrics) {
+                        Ok(counts) => file_counts.extend(counts),
+                        Err(diagnostic) => diagnostics.push(diagnostic),
+                    }
+                }
+                Stolen::Data(Work::Buffer(path, bytes, attributes)) => {
+                    self.queued.fetch_sub(1, Ordering::SeqCst);
+                    let language = attributes.language.unwrap_or_else(|| get_language(&path));
+                    if language == Language::Unknown {
+                        continue;
+                    }
+                    match count_buffer(path, language, &bytes, &attributes, &self.filters, self.metrics) {
+                        Ok(counts) => file_counts.extend(counts),
+                        Err(diagnostic) => diagnostics.push(diagnostic),
+                    }
                 }
                 _ => continue,
             }
         }
-        file_counts
+        (file_counts, diagnostics)
+    }
+}
+
+// Counts the contents of the file at path, applying the filters. Literate
+// sources give one count for each language they contain.
+fn count_buffer(path: String,
+                language: Language,
+                bytes: &[u8],
+                attributes: &Attributes,
+                filters: &Filters,
+                metrics: Metrics) -> Result<Vec<FileCount>,Diagnostic> {
+    if attributes.binary || looks_binary(bytes) {
+        return Err(Diagnostic {
+            path,
+            reason: "binary file",
+        });
+    }
+
+    let generated = filters.generated != Mode::Include && attributes.generated.unwrap_or_else(|| {
+        is_generated_name(&path) || has_generated_header(bytes)
+    });
+    if generated && filters.generated == Mode::Exclude {
+        return Ok(vec![]);
+    }
+    let minified = filters.minified != Mode::Include
+        && (is_minified_name(&path) || looks_minified(bytes, language));
+    if minified && filters.minified == Mode::Exclude {
+        return Ok(vec![]);
     }
+    let vendored = filters.vendored != Mode::Include && attributes.vendored.unwrap_or_else(|| {
+        filters.vendored_patterns.is_vendored(&path)
+    });
+    if vendored && filters.vendored == Mode::Exclude {
+        return Ok(vec![]);
+    }
+
+    let file_counts = file_parts(&path, language, bytes)
+        .iter()
+        .map(|part| FileCount {
+            language: part.language,
+            path: path.clone(),
+            count: part.count(),
+            prose: part.prose(),
+            host: part.host,
+            complexity: if metrics.complexity {
+                count_complexity_bytes(&part.bytes, part.language)
+            } else {
+                None
+            },
+            definitions: if metrics.definitions {
+                count_definitions_bytes(&part.bytes, part.language)
+            } else {
+                None
+            },
+            logical: if metrics.logical {
+                count_logical_bytes(&part.bytes, part.language)
+            } else {
+                None
+            },
+            generated,
+            minified,
+            vendored,
+        })
+        .collect();
+    Ok(file_counts)
+}
+
+// A file that was skipped instead of counted
+#[derive(Clone)]
+struct Diagnostic {
+    path: String,
+    reason: &'static str,
 }
 
 #[derive(Clone)]
@@ -83,11 +247,35 @@ struct FileCount {
     path: String,
     language: language,
     count: Count,
+    prose: Option<Prose>,
+    host: Option<Language>,
+    complexity: Option<u32>,
+    definitions: Option<Definitions>,
+    logical: Option<u32>,
+    generated: bool,
+    minified: bool,
+    vendored: bool,
 }
 
 struct LanguageTotal {
     files: u32,
     count: Count,
+    logical: Option<u32>,
+}
+
+// A file with a complexity estimate
+struct FileComplexity {
+    path: String,
+    code: u32,
+    complexity: u32,
+}
+
+// The targets given on the command line, or the current directory
+fn targets<'a>(matches: &'a ArgMatches) -> Vec<&'a str> {
+    match matches.values_of("target") {
+        Some(targets) => targets.collect(),
+        None => vec!["."],
+    }
 }
 
 fn main() {
@@ -111,18 +299,335 @@ fn main() {
                 .value_name("COLUMN")
                 .help("Column to short by"),
         )
+        .arg(
+            Arg::with_name("annotate")
+                .required(false)
+                .long("annotate")
+                .takes_value(false)
+                .help("Print each target file with the classification of every line"),
+        )
+        .arg(
+            Arg::with_name("complexity")
+                .required(false)
+                .long("complexity")
+                .takes_value(false)
+                .help("Estimate cyclomatic complexity from the decision points in the code"),
+        )
+        .arg(
+            Arg::with_name("definitions")
+                .required(false)
+                .long("definitions")
+                .takes_value(false)
+                .help("Count function and type definitions and the average function length"),
+        )
+        .arg(
+            Arg::with_name("logical")
+                .required(false)
+                .long("logical")
+                .takes_value(false)
+                .help("Add a column of logical lines, counting statements instead of lines"),
+        )
+        .arg(
+            Arg::with_name("data")
+                .required(false)
+                .long("data")
+                .takes_value(false)
+                .help("Include data formats (JSON, YAML, TOML, INI, XML) in the code totals"),
+        )
+        .arg(
+            Arg::with_name("generated")
+                .required(false)
+                .long("generated")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["include", "exclude", "separate"])
+                .help("How to count generated files: include, exclude or separate. Default: include"),
+        )
+        .arg(
+            Arg::with_name("minified")
+                .required(false)
+                .long("minified")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["include", "exclude", "separate"])
+                .help("How to count minified and bundled files: include, exclude or separate. Default: include"),
+        )
+        .arg(
+            Arg::with_name("vendored")
+                .required(false)
+                .long("vendored")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["include", "exclude", "separate"])
+                .help("How to count vendored and third-party code: include, exclude or separate. Default: include"),
+        )
+        .arg(
+            Arg::with_name("submodules")
+                .required(false)
+                .long("submodules")
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(&["include", "exclude", "separate"])
+                .conflicts_with_all(&["rev", "changed-since", "git-tracked"])
+                .help("How to count git submodules: include, exclude or separate. Default: include"),
+        )
+        .arg(
+            Arg::with_name("vendor-pattern")
+                .required(false)
+                .long("vendor-pattern")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("GLOB")
+                .help("Additional location to treat as vendored (Multiple patterns allowed)"),
+        )
+        .arg(
+            Arg::with_name("rev")
+                .required(false)
+                .long("rev")
+                .takes_value(true)
+                .value_name("REF")
+                .help("Count the tree of a git revision instead of the working directory"),
+        )
+        .arg(
+            Arg::with_name("changed-since")
+                .required(false)
+                .long("changed-since")
+                .takes_value(true)
+                .value_name("REF")
+                .conflicts_with("rev")
+                .help("Only count files that differ from a git revision, and untracked files"),
+        )
+        .arg(
+            Arg::with_name("git-tracked")
+                .required(false)
+                .long("git-tracked")
+                .takes_value(false)
+                .conflicts_with_all(&["rev", "changed-since"])
+                .help("Count the files in the git index instead of walking the directories"),
+        )
+        .arg(
+            Arg::with_name("stdin")
+                .required(false)
+                .long("stdin")
+                .takes_value(false)
+                .help("Count source read from stdin, same as giving - as the target"),
+        )
+        .arg(
+            Arg::with_name("stdin-lang")
+                .required(false)
+                .long("stdin-lang")
+                .takes_value(true)
+                .value_name("LANGUAGE")
+                .help("Language of the source read from stdin"),
+        )
+        .arg(
+            Arg::with_name("stdin-name")
+                .required(false)
+                .long("stdin-name")
+                .takes_value(true)
+                .value_name("NAME")
+                .help("File name used to detect the language of the source read from stdin"),
+        )
         .arg(
             Arg::with_name("target")
-                .required(true)
+                .required_unless("stdin")
                 .multiple(true)
                 .help("File or directory to count line in (Multiple targets allowed)"),
         )
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Count added, removed, modified and unchanged lines between two versions")
+                .arg(
+                    Arg::with_name("files")
+                        .required(false)
+                        .long("files")
+                        .takes_value(false)
+                        .help("Show changes for each individual file"),
+                )
+                .arg(
+                    Arg::with_name("old")
+                        .required(true)
+                        .help("Directory or git revision to compare from"),
+                )
+                .arg(
+                    Arg::with_name("new")
+                        .required(true)
+                        .help("Directory or git revision to compare to"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("history")
+                .about("Count lines per language at sampled commits of the current branch")
+                .arg(
+                    Arg::with_name("since")
+                        .required(false)
+                        .long("since")
+                        .takes_value(true)
+                        .help("Only sample commits more recent than this date"),
+                )
+                .arg(
+                    Arg::with_name("step")
+                        .required(false)
+                        .long("step")
+                        .takes_value(true)
+                        .help("Sample every N commits, or the last commit of each week with weekly (default 1)"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .required(false)
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["csv", "json"])
+                        .help("Output format (default csv)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("authors")
+                .about("Split lines by the author who last changed them, using git blame")
+                .arg(
+                    Arg::with_name("target")
+                        .required(false)
+                        .multiple(true)
+                        .help("File or directory to count line in (Multiple targets allowed)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("age")
+                .about("Count code lines by how long ago they were last changed, using git blame")
+                .arg(
+                    Arg::with_name("files")
+                        .required(false)
+                        .long("files")
+                        .takes_value(false)
+                        .help("Show code age for each individual file"),
+                )
+                .arg(
+                    Arg::with_name("target")
+                        .required(false)
+                        .multiple(true)
+                        .help("File or directory to count line in (Multiple targets allowed)"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("hotspots")
+                .about("Rank files by size and by how often they changed recently")
+                .arg(
+                    Arg::with_name("since")
+                        .required(false)
+                        .long("since")
+                        .takes_value(true)
+                        .help("Only count commits more recent than this date (default 1 year ago)"),
+                )
+                .arg(
+                    Arg::with_name("size-weight")
+                        .required(false)
+                        .long("size-weight")
+                        .takes_value(true)
+                        .help("Weight of the lines of code in the score (default 1)"),
+                )
+                .arg(
+                    Arg::with_name("churn-weight")
+                        .required(false)
+                        .long("churn-weight")
+                        .takes_value(true)
+                        .help("Weight of the number of commits in the score (default 1)"),
+                )
+                .arg(
+                    Arg::with_name("limit")
+                        .required(false)
+                        .long("limit")
+                        .takes_value(true)
+                        .help("Only show this many files"),
+                )
+                .arg(
+                    Arg::with_name("format")
+                        .required(false)
+                        .long("format")
+                        .takes_value(true)
+                        .possible_values(&["table", "json"])
+                        .help("Output format (default table)"),
+                )
+                .arg(
+                    Arg::with_name("target")
+                        .required(false)
+                        .multiple(true)
+                        .help("File or directory to count line in (Multiple targets allowed)"),
+                ),
+        )
         .get_matches();
 
-    let targets = match matches.values_of("target") {
-        Some(targets) => targets.collect(),
-        None => vec!["."],
-    };
+    if let Some(matches) = matches.subcommand_matches("hotspots") {
+        let targets = targets(matches);
+        let mut weights = [1.0, 1.0];
+        for (weight, name) in weights.iter_mut().zip(&["size-weight", "churn-weight"]) {
+            if let Some(value) = matches.value_of(name) {
+                match value.parse::<f64>() {
+                    Ok(value) if value >= 0.0 => *weight = value,
+                    _ => {
+                        println!("Error: invalid value for --{}: {}", name, value);
+                        return;
+                    }
+                }
+            }
+        }
+        let limit = match matches.value_of("limit").map(|limit| limit.parse::<usize>()) {
+            None => usize::MAX,
+            Some(Ok(limit)) => limit,
+            Some(Err(_)) => {
+                println!("Error: invalid value for --limit: {}", matches.value_of("limit").unwrap());
+                return;
+            }
+        };
+        let since = matches.value_of("since").unwrap_or("1 year ago");
+        let json = matches.value_of("format") == Some("json");
+        if let Err(err) = commands::hotspots::run(&targets, since, weights, limit, json) {
+            println!("Error: {}", err);
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("age") {
+        let targets = targets(matches);
+        if let Err(err) = commands::age::run(&targets, matches.is_present("files")) {
+            println!("Error: {}", err);
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("authors") {
+        let targets = targets(matches);
+        if let Err(err) = commands::authors::run(&targets) {
+            println!("Error: {}", err);
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("history") {
+        let step = match matches.value_of("step").map(Step::from_str) {
+            None => Step::Commits(1),
+            Some(Ok(step)) => step,
+            Some(Err(_)) => {
+                println!("Error: invalid value for --step: {}", matches.value_of("step").unwrap());
+                return;
+            }
+        };
+        let json = matches.value_of("format") == Some("json");
+        if let Err(err) = commands::history::run(matches.value_of("since"), &step, json) {
+            println!("Error: {}", err);
+        }
+        return;
+    }
+
+    if let Some(matches) = matches.subcommand_matches("diff") {
+        let old = matches.value_of("old").unwrap();
+        let new = matches.value_of("new").unwrap();
+        commands::diff::run(old, new, matches.is_present("files"));
+        return;
+    }
+
+    let targets = targets(&matches);
 
     let sort: Sort = match matches.value_of("sort") {
         Some(string) => match Sort::from_str(string) {
@@ -144,12 +649,86 @@ fn main() {
     };
 
     let by_file: bool = matches.is_present("files");
+    let count_data: bool = matches.is_present("data");
+
+    if matches.is_present("annotate") {
+        for target in &targets {
+            annotate(target);
+        }
+        return;
+    }
 
     if by_file && (sort == Sort::Language || sort == Sort::Files) {
         println!("Error: cannot sort by Language or Files when --files is present");
         return;
     }
 
+    let extra_patterns: Vec<&str> = match matches.values_of("vendor-pattern") {
+        Some(patterns) => patterns.collect(),
+        None => vec![],
+    };
+    let vendored_patterns = match Vendored::new(&extra_patterns) {
+        Ok(vendored) => vendored,
+        Err(err) => {
+            println!("Error: invalid value for --vendor-pattern: {}", err);
+            return;
+        }
+    };
+
+    let filters = Filters {
+        generated: matches
+            .value_of("generated")
+            .map_or(Mode::Include, |mode| Mode::from_str(mode).unwrap()),
+        minified: matches
+            .value_of("minified")
+            .map_or(Mode::Include, |mode| Mode::from_str(mode).unwrap()),
+        vendored: matches
+            .value_of("vendored")
+            .map_or(Mode::Include, |mode| Mode::from_str(mode).unwrap()),
+        vendored_patterns: Arc::new(vendored_patterns),
+        submodules: matches
+            .value_of("submodules")
+            .map_or(Mode::Include, |mode| Mode::from_str(mode).unwrap()),
+    };
+
+    let metrics = Metrics {
+        complexity: matches.is_present("complexity"),
+        definitions: matches.is_present("definitions"),
+        logical: matches.is_present("logical"),
+    };
+
+    if matches.is_present("stdin") || targets == ["-"] {
+        let name = matches.value_of("stdin-name").unwrap_or("<stdin>");
+        if Path::new(name).file_name().is_none() {
+            println!("Error: --stdin-name must end in a file name, got \"{}\"", name);
+            return;
+        }
+        let language = match (matches.value_of("stdin-lang"), matches.value_of("stdin-name")) {
+            (Some(lang), _) => Language::from_str(lang).unwrap_or(Language::Unknown),
+            (None, Some(name)) => get_language(name),
+            (None, None) => {
+                println!("Error: reading from stdin requires --stdin-lang or --stdin-name");
+                return;
+            }
+        };
+        if language == Language::Unknown {
+            println!("Error: unknown language for the source read from stdin");
+            return;
+        }
+
+        let mut bytes = vec![];
+        if let Err(err) = io::stdin().read_to_end(&mut bytes) {
+            println!("Error: cannot read stdin: {}", err);
+            return;
+        }
+
+        match count_buffer(String::from(name), language, &bytes, &Attributes::default(), &filters, metrics) {
+            Ok(filecounts) => report(filecounts, &sort, by_file, count_data),
+            Err(diagnostic) => print_diagnostics(&[diagnostic]),
+        }
+        return;
+    }
+
     let (use_ignore, ignore_hidden) = match matches.occurrences_of("unrestricted") {
         0 => (true, true),
         1 => (false, true),
@@ -160,16 +739,188 @@ fn main() {
     let threads = num_cpus::get();
     let mut workers = vec![];
     let (workque, stealer) = deque::new();
+    let queued = Arc::new(AtomicUsize::new(0));
     for _ in 0..threads {
         let worker = Worker {
             stealer: stealer.clone(),
+            queued: queued.clone(),
+            filters: filters.clone(),
+            metrics,
         };
         workers.push(thread::spawn(|| worker.run()));
     }
-    
-    let mut paths_iter = targets.iter();
+
+    // Work is handed to the workers while it is found, and the queue is kept
+    // short so files read into memory do not pile up ahead of the counting
+    let limit = threads * 4;
+    let (tx,rx) = sync_channel(limit);
+    let feeder = thread::spawn(move || {
+        for work in rx {
+            while queued.load(Ordering::SeqCst) >= limit {
+                thread::yield_now();
+            }
+            queued.fetch_add(1, Ordering::SeqCst);
+            workque.push(work);
+        }
+        for _ in 0..threads {
+            workque.push(Work::Quit);
+        }
+    });
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+    // Archives given as targets are read here and their files handed to the
+    // workers as buffers
+    let (archives, paths): (Vec<&str>, Vec<&str>) = targets
+        .iter()
+        .partition(|target| is_archive(target) && Path::new(target).is_file());
+
+    for archive in archives {
+        let result = read_archive(archive, |path, bytes| {
+            tx.send(Work::Buffer(path, bytes, Attributes::default())).unwrap();
+        });
+        if result.is_err() {
+            diagnostics.push(Diagnostic {
+                path: String::from(archive),
+                reason: "unreadable archive",
+            });
+        }
+    }
+
+    let mut submodules = vec![];
+    if let Some(rev) = matches.value_of("rev") {
+        if let Err(err) = walk_rev(&paths, rev, &filters, &tx) {
+            println!("Error: cannot read revision {}: {}", rev, err);
+            return;
+        }
+    } else if let Some(rev) = matches.value_of("changed-since") {
+        match git::changed_files(".", rev, &paths) {
+            Ok(files) => send_files(files, &filters, &tx),
+            Err(err) => {
+                println!("Error: cannot compare with revision {}: {}", rev, err);
+                return;
+            }
+        }
+    } else if matches.is_present("git-tracked") {
+        match git::ls_files(".", &paths) {
+            Ok(mut files) => {
+                // Files deleted from the working tree and submodules
+                files.retain(|file| Path::new(file).is_file());
+                send_files(files, &filters, &tx);
+            }
+            Err(err) => {
+                println!("Error: cannot list tracked files: {}", err);
+                return;
+            }
+        }
+    } else if !paths.is_empty() {
+        submodules = walk_paths(&paths, use_ignore, ignore_hidden, threads, &filters, &tx);
+    }
+
+    drop(tx);
+    feeder.join().unwrap();
+
+    let mut filecounts: Vec<FileCount> = Vec::new();
+    for worker in workers {
+        let (worker_counts, worker_diagnostics) = worker.join().unwrap();
+        filecounts.extend(worker_counts.iter().cloned());
+        diagnostics.extend(worker_diagnostics.iter().cloned());
+    }
+
+    let mut buckets: BTreeMap<Option<String>,Vec<FileCount>> = BTreeMap::new();
+    for fc in filecounts {
+        buckets.entry(filters.bucket(&fc, &submodules)).or_default().push(fc);
+    }
+
+    for (bucket, filecounts) in buckets {
+        if let Some(title) = bucket {
+            println!();
+            println!(" {}", title);
+        }
+        report(filecounts, &sort, by_file, count_data);
+    }
+
+    if !diagnostics.is_empty() {
+        print_diagnostics(&diagnostics);
+    }
+}
+
+// Prints a file with a prefix on each line: the line number, the classification
+// (Code, coMment, Blank, Doc or X for code mixed with comments) and the end
+// marker of the comment that is still open after the line.
+fn annotate(path: &str) {
+    // Targets are not walked here, so a directory can get this far
+    if Path::new(path).is_dir() || Path::new(path).file_name().is_none() {
+        println!("Error: {} is not a file", path);
+        return;
+    }
+
+    let language = get_language(path);
+    if language == Language::Unknown {
+        println!("Error: unknown language for {}", path);
+        return;
+    }
+
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            println!("Error: cannot read {}: {}", path, err);
+            return;
+        }
+    };
+
+    // Literate sources are shown one language at a time
+    for part in file_parts(path, language, &bytes) {
+        println!("{} ({})", path, part.language);
+        println!("{}", "-".repeat(80));
+
+        let mut kinds = Vec::new();
+        for line in part.lines() {
+            let kind = match line.kind {
+                LineKind::Code => 'C',
+                LineKind::Comment => 'M',
+                LineKind::Blank => 'B',
+                LineKind::Doc => 'D',
+                LineKind::Mixed => 'X',
+            };
+            kinds.push(line.kind);
+
+            println!("{0: >6} {1} {2: <6}| {3}",
+                     part.line_number(&line).map_or(String::new(), |number| number.to_string()),
+                     kind,
+                     line.open.unwrap_or(""),
+                     String::from_utf8_lossy(line.text));
+        }
+
+        // Same totals as a normal count, so invalid UTF-8 sums to nothing
+        let count = tally(&part.bytes, kinds);
+        println!("{}", "-".repeat(80));
+        println!(" Lines: {}  Blank: {}  Comments: {}  Code: {}",
+                 count.total,
+                 count.blank,
+                 count.comments,
+                 count.code);
+    }
+}
+
+fn print_diagnostics(diagnostics: &[Diagnostic]) {
+    println!();
+    println!(" Diagnostics: {} files skipped", diagnostics.len());
+    println!("{}", "-".repeat(80));
+    for diagnostic in diagnostics {
+        println!(" {0: <60} {1: >18}", diagnostic.path, diagnostic.reason);
+    }
+}
+
+// Walks the targets and returns the submodules found when they are counted
+// separately
+fn walk_paths(paths: &[&str],
+              use_ignore: bool,
+              ignore_hidden: bool,
+              threads: usize,
+              filters: &Filters,
+              tx: &SyncSender<Work>) -> Vec<String> {
+    let mut paths_iter = paths.iter();
     let first_path = paths_iter.next().expect("Error: Targets cant be empty");
-        
 
     let mut walker = WalkBuilder::new(first_path);
     walker.ignore(use_ignore)
@@ -184,14 +935,21 @@ fn main() {
 
     let quit = Arc::new(AtomicBool::new(false));
     let sender_quit = Arc::clone(&quit);
-    let (tx,rx) = channel();
+    let check_vendored = filters.vendored != Mode::Include;
+    let skip_vendored = filters.vendored == Mode::Exclude;
+    let roots: Arc<Vec<PathBuf>> = Arc::new(paths.iter().map(PathBuf::from).collect());
+    let submodules = filters.submodules;
+    let submodule_roots = Arc::new(Mutex::new(vec![]));
 
     let parallel_walker = walker.build_parallel();
     parallel_walker.run(|| {
         
         let tx_thread = tx.clone();
         let quit = Arc::clone(&sender_quit);
-        
+        let vendored_patterns = Arc::clone(&filters.vendored_patterns);
+        let roots = Arc::clone(&roots);
+        let submodule_roots = Arc::clone(&submodule_roots);
+
         Box::new(move |result| {
 
             if quit.load(Ordering::Relaxed) {
@@ -207,29 +965,99 @@ fn main() {
                 Some(ft) => ft,
                 None => return WalkState::Continue,
             };
+
+            // Vendored locations are matched below the target they were found in
+            let vendored = check_vendored && path.depth() > 0 && {
+                let relative = roots
+                    .iter()
+                    .filter_map(|root| path.path().strip_prefix(root).ok())
+                    .min_by_key(|relative| relative.components().count())
+                    .unwrap_or_else(|| path.path());
+                vendored_patterns.is_vendored(&relative.to_string_lossy())
+            };
+            if vendored && skip_vendored {
+                return if ft.is_dir() { WalkState::Skip } else { WalkState::Continue };
+            }
+
+            // A checked out submodule has a .git file or directory of its own,
+            // which git is only asked about when submodules are not included
+            if submodules != Mode::Include && ft.is_dir() && path.depth() > 0
+                && path.path().join(".git").exists() && git::is_submodule(path.path()) {
+                match submodules {
+                    Mode::Exclude => return WalkState::Skip,
+                    Mode::Separate => {
+                        let root = String::from(path.path().to_str().unwrap());
+                        submodule_roots.lock().unwrap().push(root);
+                    }
+                    Mode::Include => (),
+                }
+            }
+
             if ft.is_file() {
-                tx_thread.send(Work::File(String::from(path.path().to_str().unwrap()))).unwrap();
+                let attributes = Attributes {
+                    vendored: if check_vendored { Some(vendored) } else { None },
+                    ..Attributes::default()
+                };
+                tx_thread.send(Work::File(String::from(path.path().to_str().unwrap()), attributes)).unwrap();
             }
 
             WalkState::Continue
         })
     });
 
-    drop(tx);
-    
-    for value in rx {
-        workque.push(value);
-    }
+    let roots = submodule_roots.lock().unwrap().clone();
+    roots
+}
 
-    for _ in 0..workers.len() {
-        workque.push(Work::Quit);
+// Counts a list of files from git instead of walking the targets
+fn send_files(files: Vec<String>, filters: &Filters, tx: &SyncSender<Work>) {
+    for file in files {
+        if filters.vendored == Mode::Exclude && filters.vendored_patterns.is_vendored(&file) {
+            continue;
+        }
+        tx.send(Work::File(file, Attributes::default())).unwrap();
     }
-    
-    let mut filecounts: Vec<FileCount> = Vec::new();
-    for worker in workers {
-        filecounts.extend(worker.join().unwrap().iter().cloned());
+}
+
+// Walks the tree of a revision instead of the working directory, reading the
+// files from the object database. Targets select directories of the tree.
+fn walk_rev(paths: &[&str], rev: &str, filters: &Filters, tx: &SyncSender<Work>) -> io::Result<()> {
+    for path in paths {
+        let prefix = git::git_string(path, &["rev-parse", "--show-prefix"])?;
+        let entries = git::ls_tree(path, rev)?;
+        let mut blobs = git::BlobReader::new(path)?;
+
+        let mut attribute_files = vec![];
+        for entry in &entries {
+            if entry.path == ".gitattributes" || entry.path.ends_with("/.gitattributes") {
+                attribute_files.push((entry.path.clone(), blobs.read(&entry.oid)?));
+            }
+        }
+        let gitattributes = git::GitAttributes::parse(attribute_files);
+
+        for entry in entries {
+            if !entry.path.starts_with(&*prefix) {
+                continue;
+            }
+            let attributes = gitattributes.attributes(&entry.path);
+            let language = attributes.language.unwrap_or_else(|| get_language(&entry.path));
+            if language == Language::Unknown {
+                continue;
+            }
+            let vendored = attributes.vendored.unwrap_or_else(|| {
+                filters.vendored_patterns.is_vendored(&entry.path)
+            });
+            if vendored && filters.vendored == Mode::Exclude {
+                continue;
+            }
+            let bytes = blobs.read(&entry.oid)?;
+            tx.send(Work::Buffer(entry.path, bytes, attributes)).unwrap();
+        }
     }
+    Ok(())
+}
 
+fn report(filecounts: Vec<FileCount>, sort: &Sort, by_file: bool, count_data: bool) {
     let mut by_language: HashMap<language,Vec<FileCount>> = HashMap::new();
     for fc in filecounts {
         match by_language.entry(fc.language) {
@@ -240,58 +1068,68 @@ fn main() {
         };
     }
 
-    let linesep = "-".repeat(80);
+    let show_logical = by_language.values().any(|filecounts| filecounts.iter().any(|fc| fc.logical.is_some()));
+    let linesep = "-".repeat(if show_logical { 93 } else { 80 });
 
-    if by_file {
-
-        println!("{}",linesep);
-        println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
-                 "Language",
-                 "Files",
-                 "Lines",
-                 "Blank",
-                 "Comments",
-                 "Code");
-        println!("{}", linesep);
-
-        for (language, mut filecounts) in by_language {
-            let mut total = Count::default();
-            for fc in &filecounts {
-                total.merge(&fc.count);
+    let mut prose_by_language: Vec<(Language, u32, Prose)> = by_language
+        .iter()
+        .filter(|&(language, _)| language.is_prose())
+        .map(|(language, filecounts)| {
+            let mut total = Prose::default();
+            for fc in filecounts {
+                if let Some(ref prose) = fc.prose {
+                    total.merge(prose);
+                }
             }
+            (*language, filecounts.len() as u32, total)
+        })
+        .collect();
+    prose_by_language.sort_by_key(|(_, _, prose)| Reverse(prose.words));
 
-            println!("{}",linesep);
-            println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
-                     language,
-                     filecounts.len(),
-                     total.total,
-                     total.blank,
-                     total.comments,
-                     total.code);
-        
-             match sort {
-                Sort::Code =>
-                    filecounts.sort_by(|fc1, fc2| fc2.count.code.cmp(&fc1.count.code)),
-                Sort::Comment =>
-                    filecounts.sort_by(|fc1, fc2| fc2.count.comments.cmp(&fc1.count.comments)),
-                Sort::Blank =>
-                    filecounts.sort_by(|fc1, fc2| fc2.count.blank.cmp(&fc1.count.blank)),
-                Sort::Lines =>
-                    filecounts.sort_by(|fc1, fc2| fc2.count.total.cmp(&fc1.count.total)),
-                Sort::Language | Sort::Files => panic!("Sorting by language or files \
-                                                        when using the --sort flag"),
-             }
-        
-            println!("{}",linesep);
+    let mut complexity_by_language: Vec<(Language, Vec<FileComplexity>)> = by_language
+        .iter()
+        .map(|(language, filecounts)| {
+            let mut files: Vec<FileComplexity> = filecounts
+                .iter()
+                .filter_map(|fc| fc.complexity.map(|complexity| FileComplexity {
+                    path: fc.path.clone(),
+                    code: fc.count.code,
+                    complexity,
+                }))
+                .collect();
+            files.sort_by_key(|file| Reverse(file.complexity));
+            (*language, files)
+        })
+        .filter(|(_, files)| !files.is_empty())
+        .collect();
+    complexity_by_language.sort_by_key(|(_, files)| {
+        Reverse(files.iter().map(|file| file.complexity).sum::<u32>())
+    });
 
-            for fc in filecounts {
-                println!("|{0: <25} {1: >12} {2: >12} {3: >12} {4: >12}",
-                         fc.path,
-                         fc.count.total,
-                         fc.count.blank,
-                         fc.count.comments,
-                         fc.count.code);
-            }
+    let mut definitions_by_language: Vec<(Language, Vec<(String, Definitions)>)> = by_language
+        .iter()
+        .map(|(language, filecounts)| {
+            let mut files: Vec<(String, Definitions)> = filecounts
+                .iter()
+                .filter_map(|fc| fc.definitions.clone().map(|definitions| (fc.path.clone(), definitions)))
+                .collect();
+            files.sort_by_key(|(_, definitions)| Reverse(definitions.functions));
+            (*language, files)
+        })
+        .filter(|(_, files)| !files.is_empty())
+        .collect();
+    definitions_by_language.sort_by_key(|(_, files)| {
+        Reverse(files.iter().map(|(_, definitions)| definitions.functions).sum::<u32>())
+    });
+
+    if by_file {
+        let (data_files, code_files): (Vec<_>, Vec<_>) = by_language
+            .into_iter()
+            .partition(|&(language, _)| !count_data && language.is_data());
+
+        print_files_by_language(&linesep, "Language", "Code", code_files, sort, show_logical);
+        if !data_files.is_empty() {
+            print_files_by_language(&linesep, "Data format", "Content", data_files, sort, show_logical);
         }
     } else  {
 
@@ -299,13 +1137,16 @@ fn main() {
         for (language, filecounts) in &by_language {
 
             let mut language_total = Count::default();
+            let mut logical = None;
             for fc in filecounts {
                 language_total.merge(&fc.count);
+                logical = add_logical(logical, fc.logical);
             }
 
             language_totals.insert(language,LanguageTotal {
                 files: filecounts.len() as u32,
                 count: language_total,
+                logical,
             });
         }
 
@@ -313,46 +1154,292 @@ fn main() {
         
         match sort {
             Sort::Language => totals_by_language
-                .sort_by(|&(l1, _), &(l2, _)| l1.to_string().cmp(&l2.to_string())),
+                .sort_by_key(|&(l1, _)| l1.to_string()),
              Sort::Files => totals_by_language
-                  .sort_by(|&(_, c1), &(_, c2)| c2.files.cmp(&c1.files)),
+                  .sort_by_key(|&(_, total)| Reverse(total.files)),
             Sort::Code => totals_by_language
-                .sort_by(|&(_, c1), &(_, c2)| c2.count.code.cmp(&c1.count.code)),
+                .sort_by_key(|&(_, total)| Reverse(total.count.code)),
             Sort::Comment => totals_by_language
-                .sort_by(|&(_, c1), &(_, c2)| c2.count.comments.cmp(&c1.count.comments)),
+                .sort_by_key(|&(_, total)| Reverse(total.count.comments)),
             Sort::Blank => totals_by_language
-                .sort_by(|&(_, c1), &(_, c2)| c2.count.blank.cmp(&c1.count.blank)),
+                .sort_by_key(|&(_, total)| Reverse(total.count.blank)),
             Sort::Lines => totals_by_language
-                .sort_by(|&(_, c1), &(_, c2)| c2.count.total.cmp(&c1.count.total)),
+                .sort_by_key(|&(_, total)| Reverse(total.count.total)),
         }    
-        print_totals_by_language(&linesep,&totals_by_language);
+
+        let (data_totals, code_totals): (Vec<_>, Vec<_>) = totals_by_language
+            .into_iter()
+            .partition(|&(language, _)| !count_data && language.is_data());
+
+        print_totals_by_language(&linesep, "Language", "Code", &code_totals, show_logical);
+        if !data_totals.is_empty() {
+            print_totals_by_language(&linesep, "Data format", "Content", &data_totals, show_logical);
+        }
+    }
+
+    if !prose_by_language.is_empty() {
+        print_prose_by_language(&linesep, &prose_by_language);
+    }
+
+    if !complexity_by_language.is_empty() {
+        print_complexity_by_language(&linesep, &complexity_by_language, by_file);
+    }
+
+    if !definitions_by_language.is_empty() {
+        print_definitions_by_language(&linesep, &definitions_by_language, by_file);
+    }
+}
+
+fn print_definitions_by_language(linesep: &str,
+                                 definitions_by_language: &[(Language, Vec<(String, Definitions)>)],
+                                 by_file: bool) {
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
+             "Language",
+             "Files",
+             "Functions",
+             "Types",
+             "Impls",
+             "Avg function");
+    println!("{}", linesep);
+
+    let mut totals = Definitions::default();
+    let mut total_files = 0;
+    for &(language, ref files) in definitions_by_language {
+        let mut language_total = Definitions::default();
+        for (_, definitions) in files {
+            language_total.merge(definitions);
+        }
+        if by_file {
+            println!("{}", linesep);
+        }
+        println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12.1}",
+                 language,
+                 files.len(),
+                 language_total.functions,
+                 language_total.types,
+                 language_total.impls,
+                 language_total.average_function_lines());
+        if by_file {
+            println!("{}", linesep);
+            for (path, definitions) in files {
+                println!("|{0: <25} {1: >12} {2: >12} {3: >12} {4: >12.1}",
+                         path,
+                         definitions.functions,
+                         definitions.types,
+                         definitions.impls,
+                         definitions.average_function_lines());
+            }
+        }
+        total_files += files.len();
+        totals.merge(&language_total);
+    }
+
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12.1}",
+             "Total",
+             total_files,
+             totals.functions,
+             totals.types,
+             totals.impls,
+             totals.average_function_lines());
+    println!("{}", linesep);
+}
+
+fn per_code_line(complexity: u32, code: u32) -> f64 {
+    if code == 0 {
+        0.0
+    } else {
+        f64::from(complexity) / f64::from(code)
+    }
+}
+
+fn print_complexity_by_language(linesep: &str,
+                                complexity_by_language: &[(Language, Vec<FileComplexity>)],
+                                by_file: bool) {
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12}",
+             "Language",
+             "Files",
+             "Code",
+             "Complexity",
+             "Per line");
+    println!("{}", linesep);
+
+    let (mut total_files, mut total_code, mut total_complexity) = (0, 0, 0);
+    for &(language, ref files) in complexity_by_language {
+        let code = files.iter().map(|file| file.code).sum();
+        let complexity = files.iter().map(|file| file.complexity).sum();
+        if by_file {
+            println!("{}", linesep);
+        }
+        println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12.2}",
+                 language,
+                 files.len(),
+                 code,
+                 complexity,
+                 per_code_line(complexity, code));
+        if by_file {
+            println!("{}", linesep);
+            for file in files {
+                println!("|{0: <25} {1: >12} {2: >12} {3: >12.2}",
+                         file.path,
+                         file.code,
+                         file.complexity,
+                         per_code_line(file.complexity, file.code));
+            }
+        }
+        total_files += files.len();
+        total_code += code;
+        total_complexity += complexity;
+    }
+
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12.2}",
+             "Total",
+             total_files,
+             total_code,
+             total_complexity,
+             per_code_line(total_complexity, total_code));
+    println!("{}", linesep);
+}
+
+fn print_files_by_language(linesep: &str,
+                           language_header: &str,
+                           code_header: &str,
+                           files_by_language: Vec<(Language, Vec<FileCount>)>,
+                           sort: &Sort,
+                           show_logical: bool) {
+    println!("{}",linesep);
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}{6}",
+             language_header,
+             "Files",
+             "Lines",
+             "Blank",
+             "Comments",
+             code_header,
+             logical_column(show_logical, Some("Logical")));
+    println!("{}", linesep);
+
+    for (language, mut filecounts) in files_by_language {
+        let mut total = Count::default();
+        let mut logical = None;
+        for fc in &filecounts {
+            total.merge(&fc.count);
+            logical = add_logical(logical, fc.logical);
+        }
+
+        println!("{}",linesep);
+        println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}{6}",
+                 language,
+                 filecounts.len(),
+                 total.total,
+                 total.blank,
+                 total.comments,
+                 total.code,
+                 logical_column(show_logical, logical));
+    
+         match sort {
+            Sort::Code =>
+                filecounts.sort_by_key(|fc| Reverse(fc.count.code)),
+            Sort::Comment =>
+                filecounts.sort_by_key(|fc| Reverse(fc.count.comments)),
+            Sort::Blank =>
+                filecounts.sort_by_key(|fc| Reverse(fc.count.blank)),
+            Sort::Lines =>
+                filecounts.sort_by_key(|fc| Reverse(fc.count.total)),
+            Sort::Language | Sort::Files => panic!("Sorting by language or files \
+                                                    when using the --sort flag"),
+         }
+    
+        println!("{}",linesep);
+
+        for fc in filecounts {
+            let mut name = match fc.host {
+                Some(host) => format!("{} ({})", fc.path, host),
+                None => fc.path,
+            };
+            if fc.minified {
+                name.push_str(" [minified]");
+            }
+            println!("|{0: <25} {1: >12} {2: >12} {3: >12} {4: >12}{5}",
+                     name,
+                     fc.count.total,
+                     fc.count.blank,
+                     fc.count.comments,
+                     fc.count.code,
+                     logical_column(show_logical, fc.logical));
+        }
     }
 }
 
-fn print_totals_by_language(linesep: &str, totals_by_language: &[(&&language, &LanguageTotal)]) {
+fn print_prose_by_language(linesep: &str, prose_by_language: &[(Language, u32, Prose)]) {
     println!("{}", linesep);
     println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
              "Language",
              "Files",
+             "Words",
+             "Headings",
+             "Code blocks",
+             "Links");
+    println!("{}", linesep);
+
+    let mut totals = Prose::default();
+    let mut files = 0;
+    for &(language, language_files, ref prose) in prose_by_language {
+        println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
+                 language,
+                 language_files,
+                 prose.words,
+                 prose.headings,
+                 prose.code_block_lines,
+                 prose.links);
+        files += language_files;
+        totals.merge(prose);
+    }
+
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
+             "Total",
+             files,
+             totals.words,
+             totals.headings,
+             totals.code_block_lines,
+             totals.links);
+    println!("{}", linesep);
+}
+
+fn print_totals_by_language(linesep: &str,
+                            language_header: &str,
+                            code_header: &str,
+                            totals_by_language: &[(&&language, &LanguageTotal)],
+                            show_logical: bool) {
+    println!("{}", linesep);
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}{6}",
+             language_header,
+             "Files",
              "Lines",
              "Blank",
              "Comments",
-             "Code");
+             code_header,
+             logical_column(show_logical, Some("Logical")));
     println!("{}", linesep);
 
     for &(language, total) in totals_by_language {
-        println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
+        println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}{6}",
                  language,
                  total.files,
                  total.count.total,
                  total.count.blank,
                  total.count.comments,
-                 total.count.code);
+                 total.count.code,
+                 logical_column(show_logical, total.logical));
     }
 
     let mut totals = LanguageTotal {
         files: 0,
         count: Count::default(),
+        logical: None,
     };
     for &(_, total) in totals_by_language {
         totals.files += total.files;
@@ -360,16 +1447,35 @@ fn print_totals_by_language(linesep: &str, totals_by_language: &[(&&language, &L
         totals.count.blank += total.count.blank;
         totals.count.comments += total.count.comments;
         totals.count.total += total.count.total;
+        totals.logical = add_logical(totals.logical, total.logical);
     }
 
     println!("{}", linesep);
-    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}",
+    println!(" {0: <17} {1: >8} {2: >12} {3: >12} {4: >12} {5: >12}{6}",
              "Total",
              totals.files,
              totals.count.total,
              totals.count.blank,
              totals.count.comments,
-             totals.count.code);
+             totals.count.code,
+             logical_column(show_logical, totals.logical));
     println!("{}", linesep);
 }
 
+// Logical lines of a group of files, None if no file in it has them
+fn add_logical(total: Option<u32>, logical: Option<u32>) -> Option<u32> {
+    match (total, logical) {
+        (Some(total), Some(logical)) => Some(total + logical),
+        (total, None) => total,
+        (None, logical) => logical,
+    }
+}
+
+// The logical lines column, left out of the table unless --logical was given
+fn logical_column<T: ToString>(shown: bool, value: Option<T>) -> String {
+    if !shown {
+        return String::new();
+    }
+    format!(" {: >12}", value.map_or(String::from("-"), |value| value.to_string()))
+}
+
@@ -0,0 +1,322 @@
+use {get_language, map_file, ByteLines, Language};
+use Language::*;
+
+#[derive(PartialEq, Default, Clone)]
+pub struct Prose {
+    pub words: u32,
+    pub headings: u32,
+    pub code_block_lines: u32,
+    pub links: u32,
+}
+
+impl Prose {
+    pub fn merge(&mut self, prose2: &Prose) {
+        self.words += prose2.words;
+        self.headings += prose2.headings;
+        self.code_block_lines += prose2.code_block_lines;
+        self.links += prose2.links;
+    }
+}
+
+#[derive(Default)]
+struct ProseState {
+    // Fence or environment that closes the current code block, if inside one
+    block_end: Option<String>,
+    // Indentation of the line that opened a reStructuredText literal block
+    literal_indent: Option<usize>,
+    // Length of the previous line when it was plain text, used for underlined headings
+    previous_text: Option<usize>,
+}
+
+pub fn count_prose(filepath: &str) -> Prose {
+    match map_file(filepath) {
+        Some(mmap) => count_prose_bytes(&mmap, get_language(filepath)),
+        None => Prose::default(),
+    }
+}
+
+pub fn count_prose_bytes(bytes: &[u8], language: Language) -> Prose {
+    let mut prose = Prose::default();
+    let mut state = ProseState::default();
+
+    for byte_line in ByteLines(bytes).lines() {
+        let line = match ::std::str::from_utf8(byte_line) {
+            Ok(s) => s,
+            Err(_) => return Prose::default(),
+        };
+
+        match language {
+            Markdown => markdown_line(line, &mut state, &mut prose),
+            ReStructuredText => rst_line(line, &mut state, &mut prose),
+            AsciiDoc => asciidoc_line(line, &mut state, &mut prose),
+            Latex => latex_line(line, &mut state, &mut prose),
+            Org => org_line(line, &mut state, &mut prose),
+            _ => prose.words += count_words(line),
+        }
+    }
+
+    prose
+}
+
+fn count_words(text: &str) -> u32 {
+    text.split_whitespace()
+        .filter(|word| word.chars().any(|c| c.is_alphanumeric()))
+        .count() as u32
+}
+
+fn is_underline(line: &str, chars: &str) -> bool {
+    let mut line_chars = line.chars();
+    match line_chars.next() {
+        Some(first) if chars.contains(first) => line_chars.all(|c| c == first),
+        _ => false,
+    }
+}
+
+fn markdown_line(line: &str, state: &mut ProseState, prose: &mut Prose) {
+    let trimmed = line.trim();
+
+    if let Some(fence) = state.block_end.take() {
+        if !trimmed.starts_with(&*fence) {
+            prose.code_block_lines += 1;
+            state.block_end = Some(fence);
+        }
+        return;
+    }
+
+    if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+        state.block_end = Some(String::from(&trimmed[..3]));
+        state.previous_text = None;
+        return;
+    }
+
+    if trimmed.is_empty() {
+        state.previous_text = None;
+        return;
+    }
+
+    if state.previous_text.is_some() && trimmed.len() >= 2 && is_underline(trimmed, "=-") {
+        prose.headings += 1;
+        state.previous_text = None;
+        return;
+    }
+
+    let text = trimmed.trim_start_matches('#');
+    if text.len() < trimmed.len() && (text.is_empty() || text.starts_with(' ')) {
+        prose.headings += 1;
+        state.previous_text = None;
+    } else {
+        state.previous_text = Some(trimmed.len());
+    }
+
+    prose.links += (text.matches("](").count() + text.matches("<http").count()) as u32;
+    prose.words += count_words(text);
+}
+
+fn rst_line(line: &str, state: &mut ProseState, prose: &mut Prose) {
+    let trimmed = line.trim();
+    let indent = line.len() - line.trim_start().len();
+
+    if let Some(literal_indent) = state.literal_indent {
+        if trimmed.is_empty() {
+            return;
+        }
+        if indent > literal_indent {
+            prose.code_block_lines += 1;
+            return;
+        }
+        state.literal_indent = None;
+    }
+
+    if trimmed.is_empty() {
+        state.previous_text = None;
+        return;
+    }
+
+    if let Some(text_len) = state.previous_text {
+        if trimmed.len() >= text_len && is_underline(trimmed, "=-~^\"'`#*+:._") {
+            prose.headings += 1;
+            state.previous_text = None;
+            return;
+        }
+    }
+
+    if trimmed.ends_with("::") {
+        state.literal_indent = Some(indent);
+    }
+
+    state.previous_text = Some(trimmed.len());
+    prose.links += trimmed.matches("`_").count() as u32;
+    if !trimmed.starts_with("..") {
+        prose.words += count_words(trimmed);
+    }
+}
+
+fn asciidoc_line(line: &str, state: &mut ProseState, prose: &mut Prose) {
+    let trimmed = line.trim();
+    let is_delimiter = trimmed.len() >= 4 && is_underline(trimmed, "-.");
+
+    if let Some(delimiter) = state.block_end.take() {
+        if trimmed != delimiter {
+            prose.code_block_lines += 1;
+            state.block_end = Some(delimiter);
+        }
+        return;
+    }
+
+    // Delimited blocks close with the same line, a fence that names its
+    // language (```ruby) with a bare ```
+    if is_delimiter {
+        state.block_end = Some(String::from(trimmed));
+        return;
+    }
+    if trimmed.starts_with("```") {
+        state.block_end = Some(String::from("```"));
+        return;
+    }
+
+    if trimmed.starts_with("//") {
+        return;
+    }
+
+    let text = trimmed.trim_start_matches('=');
+    if text.len() < trimmed.len() && text.starts_with(' ') {
+        prose.headings += 1;
+    }
+
+    let urls = text.matches("://").count() + text.matches("mailto:").count();
+    let macros = text.matches("link:").count() - text.matches("link:http").count()
+        + text.matches("xref:").count()
+        + text.matches("<<").count();
+    prose.links += (urls + macros) as u32;
+    prose.words += count_words(text);
+}
+
+const LATEX_HEADINGS: &[&str] = &[
+    "part",
+    "chapter",
+    "section",
+    "subsection",
+    "subsubsection",
+    "paragraph",
+];
+
+const LATEX_VERBATIM: &[&str] = &["verbatim", "lstlisting", "minted", "Verbatim"];
+
+// Position of the first % that isn't escaped. An even number of backslashes
+// before it only escape each other, as in \\% where \\ is a line break.
+fn latex_comment_start(line: &str) -> Option<usize> {
+    line.match_indices('%').map(|(n, _)| n).find(|&n| {
+        line[..n].bytes().rev().take_while(|&b| b == b'\\').count() % 2 == 0
+    })
+}
+
+fn latex_line(line: &str, state: &mut ProseState, prose: &mut Prose) {
+    let text = match latex_comment_start(line) {
+        Some(n) => &line[..n],
+        None => line,
+    };
+    let trimmed = text.trim();
+
+    if let Some(end) = state.block_end.take() {
+        if !trimmed.starts_with(&*end) {
+            prose.code_block_lines += 1;
+            state.block_end = Some(end);
+        }
+        return;
+    }
+
+    for environment in LATEX_VERBATIM {
+        if trimmed.starts_with(&*format!("\\begin{{{}}}", environment)) {
+            state.block_end = Some(format!("\\end{{{}}}", environment));
+            return;
+        }
+    }
+
+    for command in trimmed.split('\\').skip(1) {
+        let name: String = command.chars().take_while(|c| c.is_alphabetic()).collect();
+        if LATEX_HEADINGS.contains(&&*name) {
+            prose.headings += 1;
+        } else if name == "href" || name == "url" {
+            prose.links += 1;
+        }
+    }
+
+    prose.words += trimmed
+        .split_whitespace()
+        .filter(|word| !word.starts_with('\\'))
+        .filter(|word| word.chars().any(|c| c.is_alphanumeric()))
+        .count() as u32;
+}
+
+fn org_line(line: &str, state: &mut ProseState, prose: &mut Prose) {
+    let trimmed = line.trim();
+    let upper = trimmed.to_uppercase();
+
+    if let Some(end) = state.block_end.take() {
+        if !upper.starts_with(&*end) {
+            prose.code_block_lines += 1;
+            state.block_end = Some(end);
+        }
+        return;
+    }
+
+    if upper.starts_with("#+BEGIN_SRC") || upper.starts_with("#+BEGIN_EXAMPLE") {
+        state.block_end = Some(String::from("#+END_"));
+        return;
+    }
+
+    if trimmed.starts_with('#') {
+        return;
+    }
+
+    let text = line.trim_start_matches('*');
+    if text.len() < line.len() && text.starts_with(' ') {
+        prose.headings += 1;
+    }
+
+    prose.links += text.matches("[[").count() as u32;
+    prose.words += count_words(text);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latex_escaped_percent_is_not_a_comment() {
+        assert_eq!(latex_comment_start("50\\% done % note"), Some(10));
+        assert_eq!(latex_comment_start("line\\\\% comment"), Some(6));
+        assert_eq!(latex_comment_start("no \\% comment"), None);
+
+        let prose = count_prose_bytes(b"50\\% done % note and more\n", Language::Latex);
+        assert_eq!(prose.words, 2);
+    }
+
+    fn fields(prose: &Prose) -> (u32, u32, u32, u32) {
+        (prose.words, prose.headings, prose.code_block_lines, prose.links)
+    }
+
+    #[test]
+    fn markdown_headings_fences_and_links() {
+        let text = b"# Title\n\nSome [words](http://a) here.\n\nSub\n---\n\n```rust\nfn main() {}\n```\nDone.\n";
+        assert_eq!(fields(&count_prose_bytes(text, Language::Markdown)), (6, 2, 1, 1));
+    }
+
+    #[test]
+    fn asciidoc_fence_with_a_language_is_closed() {
+        let text = b"= Title\n\n```ruby\nputs 1\n```\nSome words here. More words.\n----\ncode\n----\n";
+        assert_eq!(fields(&count_prose_bytes(text, Language::AsciiDoc)), (6, 1, 2, 0));
+    }
+
+    #[test]
+    fn rst_underlined_headings_and_literal_blocks() {
+        let text = b"Title\n=====\n\nSee `this <http://a>`_ example::\n\n    code\n    more\n\nBack to text.\n";
+        assert_eq!(fields(&count_prose_bytes(text, Language::ReStructuredText)), (8, 1, 2, 1));
+    }
+
+    #[test]
+    fn org_headings_source_blocks_and_links() {
+        let text = b"* Heading\n#+BEGIN_SRC python\nprint(1)\n#+END_SRC\nA [[http://a][link]] here.\n";
+        assert_eq!(fields(&count_prose_bytes(text, Language::Org)), (4, 1, 1, 1));
+    }
+}
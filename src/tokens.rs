@@ -0,0 +1,68 @@
+// Scanning code for keywords and operators. Keywords, and any other token
+// starting with a letter, digit or underscore, only match whole words.
+
+pub(crate) fn is_word_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+// Length of the word at the start of text, 0 if there is none
+pub(crate) fn word_len(text: &[u8]) -> usize {
+    text.iter().position(|&b| !is_word_byte(b)).unwrap_or(text.len())
+}
+
+// Whether token is found at pos in text
+fn token_at(text: &[u8], pos: usize, token: &str) -> bool {
+    let token = token.as_bytes();
+    text[pos..].starts_with(token)
+        && (!is_word_byte(token[0])
+            || ((pos == 0 || !is_word_byte(text[pos - 1]))
+                && !text.get(pos + token.len()).is_some_and(|&b| is_word_byte(b))))
+}
+
+// Position of the first whole word in text
+pub(crate) fn find_word(text: &[u8], word: &str) -> Option<usize> {
+    (0..(text.len() + 1).saturating_sub(word.len())).find(|&pos| token_at(text, pos, word))
+}
+
+// Counts the occurrences of tokens in code. Tokens are tried in order, so
+// longer operators must come before their prefixes. Tokens in skip are tried
+// first and passed over without being counted.
+pub(crate) fn count_tokens(code: &[u8], tokens: &[&str], skip: &[&str]) -> u32 {
+    let mut found = 0;
+    let mut pos = 0;
+    while pos < code.len() {
+        if let Some(token) = skip.iter().find(|token| token_at(code, pos, token)) {
+            pos += token.len();
+            continue;
+        }
+        match tokens.iter().find(|token| token_at(code, pos, token)) {
+            Some(token) => {
+                found += 1;
+                pos += token.len();
+            }
+            // Skip the rest of the word so keywords aren't found inside identifiers
+            None if is_word_byte(code[pos]) => pos += word_len(&code[pos..]),
+            None => pos += 1,
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords_only_match_whole_words() {
+        assert_eq!(count_tokens(b"if (iffy) elif_ = if_x; if", &["if"], &[]), 2);
+        assert_eq!(find_word(b"typedef type", "type"), Some(8));
+        assert_eq!(find_word(b"typed", "type"), None);
+    }
+
+    #[test]
+    fn operators_match_anywhere_longest_first() {
+        assert_eq!(count_tokens(b"a&&b||c&d", &["&&", "||", "&"], &[]), 3);
+        assert_eq!(count_tokens(b"x?y:z", &["?"], &[]), 1);
+        assert_eq!(count_tokens(b"a?.b ?? c ? d : e", &["??", "?"], &["?."]), 2);
+    }
+}